@@ -0,0 +1,207 @@
+//! Typed models for the price boards embedded in `__PRELOADED_STATE__`.
+//!
+//! Only the fields we use are modelled; everything else in the state is ignored.
+//! Every field is optional because the boards differ between stocks, indices and
+//! currencies, and Yahoo leaves values out while a market is closed.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+/// `mainStocksPriceBoard.priceBoard` on stock and fund pages.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceBoard {
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub code: Option<String>,
+    pub code_with_market_extension: Option<String>,
+    pub name: Option<String>,
+    pub display_name: Option<String>,
+    pub short_name: Option<String>,
+    pub market_name: Option<String>,
+    pub market_detail_code: Option<String>,
+    pub type_detail: Option<String>,
+    pub stock_type: Option<String>,
+    pub industry: Option<Industry>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub price: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub price_change: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub price_change_rate: Option<String>,
+    pub price_date_time: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub share_dividend_yield: Option<String>,
+    pub share_dividend_yield_time: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub dps: Option<String>,
+    pub dps_period: Option<String>,
+    pub us_stock: Option<UsStock>,
+}
+
+/// `priceBoard.industry`: the sector a stock is classified under.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Industry {
+    pub industry_name: Option<String>,
+    pub industry_items_link: Option<String>,
+}
+
+/// `priceBoard.usStock`: the US listing of a dual-listed stock.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UsStock {
+    pub us_market_name: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub us_price: Option<String>,
+    pub us_link: Option<String>,
+}
+
+/// `mainCurrencyPriceBoard.currencyPrices` on `=X` / `=FX` pages.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrencyPrice {
+    pub currency_pair_code: Option<String>,
+    pub currency_pair_name: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub bid: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub ask: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub price_change: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub price_change_rate: Option<String>,
+    pub price_update_time: Option<String>,
+}
+
+/// `mainDomesticIndexPriceBoard.indexPrices` on index pages such as `998407.O`.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexPrice {
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub code: Option<String>,
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub price: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub change_price: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub change_price_rate: Option<String>,
+    pub japan_update_time: Option<String>,
+}
+
+/// `mainFundPriceBoard.fundPrices` on investment trust pages such as `0331418A`.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct FundPrice {
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub fund_code: Option<String>,
+    pub fund_name: Option<String>,
+    /// 基準価額, the net asset value per 10,000 units.
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub price: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub change_price: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub change_price_rate: Option<String>,
+    /// 純資産残高, in millions of yen.
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub net_asset_balance: Option<String>,
+    pub update_date: Option<String>,
+}
+
+/// The board-independent view of a quote, keyed the way the worker reports it.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct Quote {
+    pub code: String,
+    pub name: Option<String>,
+    pub price: Option<String>,
+    pub price_change: Option<String>,
+    pub price_change_rate: Option<String>,
+    pub dividend_yield: Option<String>,
+    pub update_time: Option<String>,
+}
+
+impl From<PriceBoard> for Quote {
+    fn from(board: PriceBoard) -> Self {
+        Quote {
+            code: board.code.unwrap_or_default(),
+            name: board.name,
+            price: board.price,
+            price_change: board.price_change,
+            price_change_rate: board.price_change_rate,
+            dividend_yield: board.share_dividend_yield,
+            update_time: board.price_date_time,
+        }
+    }
+}
+
+impl From<CurrencyPrice> for Quote {
+    fn from(board: CurrencyPrice) -> Self {
+        Quote {
+            code: board.currency_pair_code.unwrap_or_default(),
+            name: board.currency_pair_name,
+            price: board.bid,
+            price_change: board.price_change,
+            price_change_rate: board.price_change_rate,
+            dividend_yield: None,
+            update_time: board.price_update_time,
+        }
+    }
+}
+
+impl From<IndexPrice> for Quote {
+    fn from(board: IndexPrice) -> Self {
+        Quote {
+            code: board.code.unwrap_or_default(),
+            name: board.name,
+            price: board.price,
+            price_change: board.change_price,
+            price_change_rate: board.change_price_rate,
+            dividend_yield: None,
+            update_time: board.japan_update_time,
+        }
+    }
+}
+
+impl From<FundPrice> for Quote {
+    fn from(board: FundPrice) -> Self {
+        Quote {
+            code: board.fund_code.unwrap_or_default(),
+            name: board.fund_name,
+            price: board.price,
+            price_change: board.change_price,
+            price_change_rate: board.change_price_rate,
+            dividend_yield: None,
+            update_time: board.update_date,
+        }
+    }
+}
+
+/// Accepts a string, number or boolean and keeps its text, so `"2,018"` and
+/// `2018` both deserialize. `null` and other shapes become `None`.
+pub(crate) fn lenient_string<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match Value::deserialize(deserializer)? {
+        Value::String(s) => Some(s),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    })
+}
+
+/// Deserializes an array one element at a time, dropping the elements that do
+/// not fit `T`, so one odd record does not lose its whole section. Anything but
+/// an array is empty.
+pub(crate) fn lenient_records<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Array(items) => items.into_iter().filter_map(|item| T::deserialize(item).ok()).collect(),
+        _ => Vec::new(),
+    })
+}
@@ -0,0 +1,114 @@
+//! Cache policy for fetched pages: keys by canonical symbol and section, and
+//! lifetimes that follow the TSE trading day.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use futures::future::{self, LocalBoxFuture};
+use serde::{Deserialize, Serialize};
+
+use crate::{tse_session, Symbol, TseSession};
+
+/// Lifetime while the market is trading, and for symbols that trade outside
+/// TSE hours (currencies, overseas indices, US stocks).
+pub const TRADING_TTL_SECONDS: u32 = 60;
+/// Lifetime between the close and the next open of a trading day.
+pub const CLOSED_TTL_SECONDS: u32 = 30 * 60;
+/// Lifetime on weekends and market holidays.
+pub const HOLIDAY_TTL_SECONDS: u32 = 6 * 60 * 60;
+
+/// How long a page of `symbol` fetched at `now` stays fresh.
+pub fn cache_ttl(symbol: &Symbol, now: DateTime<Utc>) -> u32 {
+    let follows_tse = match symbol {
+        Symbol::JpStock { .. } | Symbol::Fund(_) => true,
+        Symbol::Index(code) => !code.starts_with('^'),
+        Symbol::CurrencyX(_) | Symbol::CurrencyFx(_) | Symbol::UsStock(_) => false,
+    };
+    if !follows_tse {
+        return TRADING_TTL_SECONDS;
+    }
+    match tse_session(now) {
+        TseSession::Trading => TRADING_TTL_SECONDS,
+        TseSession::Closed => CLOSED_TTL_SECONDS,
+        TseSession::Holiday => HOLIDAY_TTL_SECONDS,
+    }
+}
+
+/// The cache key of one page of a symbol: `"7203.T:quote"`,
+/// `"7203.T:history?page=2"`. Spellings of the same symbol share a key.
+pub fn cache_key(symbol: &Symbol, section: &str) -> String {
+    format!("{}:{}", symbol.canonical(), section)
+}
+
+/// A fetched page body and when it was stored.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CachedPage {
+    pub body: String,
+    pub stored_at: DateTime<Utc>,
+    pub ttl: u32,
+}
+
+impl CachedPage {
+    pub fn new(body: String, stored_at: DateTime<Utc>, ttl: u32) -> Self {
+        CachedPage { body, stored_at, ttl }
+    }
+
+    /// Seconds since the page was stored.
+    pub fn age(&self, now: DateTime<Utc>) -> u64 {
+        (now - self.stored_at).num_seconds().max(0) as u64
+    }
+
+    pub fn is_fresh(&self, now: DateTime<Utc>) -> bool {
+        self.age(now) < u64::from(self.ttl)
+    }
+}
+
+/// Where fetched pages are kept between requests: a shared store in the
+/// worker, or a [`MemoryCache`].
+pub trait PageStore {
+    /// The page stored under `key`, if it is still fresh at `now`.
+    fn get<'a>(&'a self, key: &'a str, now: DateTime<Utc>) -> LocalBoxFuture<'a, Option<CachedPage>>;
+    /// Stores `page` under `key` for its `ttl`. A store that fails is not an
+    /// error to the caller: the page is still served.
+    fn put<'a>(&'a self, key: &'a str, page: CachedPage) -> LocalBoxFuture<'a, ()>;
+}
+
+/// An in-process page cache, used when no shared store is configured and in tests.
+#[derive(Debug, Default)]
+pub struct MemoryCache {
+    entries: HashMap<String, CachedPage>,
+}
+
+impl MemoryCache {
+    /// The page stored under `key`, if it is still fresh at `now`.
+    pub fn get(&self, key: &str, now: DateTime<Utc>) -> Option<&CachedPage> {
+        self.entries.get(key).filter(|page| page.is_fresh(now))
+    }
+
+    /// Stores `page`, dropping entries that have expired by its `stored_at`.
+    pub fn put(&mut self, key: String, page: CachedPage) {
+        let now = page.stored_at;
+        self.entries.retain(|_, entry| entry.is_fresh(now));
+        self.entries.insert(key, page);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl PageStore for RefCell<MemoryCache> {
+    fn get<'a>(&'a self, key: &'a str, now: DateTime<Utc>) -> LocalBoxFuture<'a, Option<CachedPage>> {
+        Box::pin(future::ready(self.borrow().get(key, now).cloned()))
+    }
+
+    fn put<'a>(&'a self, key: &'a str, page: CachedPage) -> LocalBoxFuture<'a, ()> {
+        self.borrow_mut().put(key.to_string(), page);
+        Box::pin(future::ready(()))
+    }
+}
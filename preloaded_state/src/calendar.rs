@@ -0,0 +1,105 @@
+//! Tokyo Stock Exchange trading hours and market holidays.
+
+use std::ops::RangeInclusive;
+
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveTime, Utc, Weekday};
+use chrono_tz::Asia::Tokyo;
+
+/// Years whose holidays follow from the current Act on National Holidays.
+/// Earlier years had one-off moves (2019 to 2021), and the equinox
+/// approximation holds until 2099.
+pub const TSE_CALENDAR_YEARS: RangeInclusive<i32> = 2022..=2099;
+
+/// Where the Tokyo market is in its trading day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TseSession {
+    /// 9:00 to 15:30 JST on a trading day, lunch break included.
+    Trading,
+    /// Before the open or after the close of a trading day.
+    Closed,
+    /// A weekend or market holiday.
+    Holiday,
+}
+
+/// Whether the TSE is closed all day on `date`: a weekend, the year-end break
+/// (Dec 31 to Jan 3) or a national holiday. `None` for a weekday outside
+/// `TSE_CALENDAR_YEARS`, whose holidays are not known.
+pub fn tse_holiday(date: NaiveDate) -> Option<bool> {
+    let (month, day) = (date.month(), date.day());
+    if matches!(date.weekday(), Weekday::Sat | Weekday::Sun) || (month == 12 && day == 31) || (month == 1 && day <= 3) {
+        return Some(true);
+    }
+    TSE_CALENDAR_YEARS.contains(&date.year()).then(|| is_national_holiday(date))
+}
+
+/// Whether the TSE is closed all day on `date`. Outside `TSE_CALENDAR_YEARS`
+/// only weekends and the year-end break are known; see [`tse_holiday`].
+pub fn is_tse_holiday(date: NaiveDate) -> bool {
+    tse_holiday(date).unwrap_or(false)
+}
+
+/// A named holiday, a substitute for one falling on a Sunday, or a weekday
+/// between two named holidays.
+fn is_national_holiday(date: NaiveDate) -> bool {
+    if is_named_holiday(date) {
+        return true;
+    }
+    let (Some(before), Some(after)) = (date.checked_sub_days(Days::new(1)), date.checked_add_days(Days::new(1))) else {
+        return false;
+    };
+    if date.weekday() != Weekday::Sun && is_named_holiday(before) && is_named_holiday(after) {
+        return true;
+    }
+    // The substitute is the first day after the Sunday that is not a holiday itself
+    let mut day = before;
+    while is_named_holiday(day) {
+        if day.weekday() == Weekday::Sun {
+            return true;
+        }
+        let Some(previous) = day.checked_sub_days(Days::new(1)) else {
+            return false;
+        };
+        day = previous;
+    }
+    false
+}
+
+fn is_named_holiday(date: NaiveDate) -> bool {
+    let (year, month, day) = (date.year(), date.month(), date.day());
+    let nth_monday = |n| NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Mon, n) == Some(date);
+    match month {
+        1 => day == 1 || nth_monday(2),
+        2 => day == 11 || day == 23,
+        3 => day == equinox_day(year, 20.8431),
+        4 => day == 29,
+        5 => (3..=5).contains(&day),
+        7 => nth_monday(3),
+        8 => day == 11,
+        9 => nth_monday(3) || day == equinox_day(year, 23.2488),
+        10 => nth_monday(2),
+        11 => day == 3 || day == 23,
+        _ => false,
+    }
+}
+
+/// Day of the month of the March (`base` 20.8431) or September (23.2488)
+/// equinox in JST, by the approximation valid from 1980 to 2099.
+fn equinox_day(year: i32, base: f64) -> u32 {
+    let since = year - 1980;
+    ((base + 0.242194 * f64::from(since)).floor() as i32 - since.div_euclid(4)) as u32
+}
+
+/// The TSE session at `now`.
+pub fn tse_session(now: DateTime<Utc>) -> TseSession {
+    let local = now.with_timezone(&Tokyo);
+    if is_tse_holiday(local.date_naive()) {
+        return TseSession::Holiday;
+    }
+    let open = NaiveTime::from_hms_opt(9, 0, 0).unwrap_or_default();
+    let close = NaiveTime::from_hms_opt(15, 30, 0).unwrap_or_default();
+    if (open..close).contains(&local.time()) {
+        TseSession::Trading
+    } else {
+        TseSession::Closed
+    }
+}
@@ -0,0 +1,119 @@
+//! The price chart embedded on quote pages: the series in
+//! `mainItemDetailChartSetting.timeSeriesData` and the display settings in
+//! `mainYJChart.chartSetting`.
+
+use chrono::{DateTime, FixedOffset};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::board::{lenient_records, lenient_string};
+use crate::parse_decimal;
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct ChartSection {
+    current_chart_term_key: Option<String>,
+    time_series_data: TimeSeriesData,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct TimeSeriesData {
+    #[serde(deserialize_with = "lenient_records")]
+    histories: Vec<SeriesRecord>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct SeriesRecord {
+    base_datetime: Option<String>,
+    datetime: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    close_price: Option<String>,
+    /// Intraday series carry a single `price` per point.
+    #[serde(deserialize_with = "lenient_string")]
+    price: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    volume: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct MainYjChart {
+    chart_setting: ChartSetting,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct ChartSetting {
+    chart_term: Option<String>,
+    chart_time_frame: Option<String>,
+    technical_indicator: TechnicalIndicator,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct TechnicalIndicator {
+    chart_over_indicator: Vec<String>,
+    chart_add_indicator: Vec<String>,
+}
+
+/// One point of the series: the closing (or intraday) price and volume.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ChartPoint {
+    pub timestamp: DateTime<FixedOffset>,
+    pub price: Option<Decimal>,
+    pub volume: Option<Decimal>,
+}
+
+/// The chart series of a quote page.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct Chart {
+    /// The term the series covers (`"1d"`, `"6m"`, ...).
+    pub term: Option<String>,
+    /// Spacing of the points (`"daily"`, `"weekly"`, ...).
+    pub time_frame: Option<String>,
+    /// Technical indicators the page overlays or adds below the chart.
+    pub indicators: Vec<String>,
+    /// Oldest first.
+    pub points: Vec<ChartPoint>,
+}
+
+/// Extracts the chart series, or `None` when the page embeds no chart.
+/// Points without a parseable timestamp are skipped.
+pub fn extract_chart(state: &Value) -> Option<Chart> {
+    let section = ChartSection::deserialize(state.get("mainItemDetailChartSetting")?).ok()?;
+    let setting = state
+        .get("mainYJChart")
+        .and_then(|s| MainYjChart::deserialize(s).ok())
+        .unwrap_or_default()
+        .chart_setting;
+
+    let mut points: Vec<ChartPoint> = section
+        .time_series_data
+        .histories
+        .into_iter()
+        .filter_map(|record| {
+            let timestamp = record.base_datetime.or(record.datetime)?;
+            Some(ChartPoint {
+                timestamp: DateTime::parse_from_rfc3339(&timestamp).ok()?,
+                price: record.close_price.or(record.price).as_deref().and_then(parse_decimal),
+                volume: record.volume.as_deref().and_then(parse_decimal),
+            })
+        })
+        .collect();
+    points.sort_by_key(|point| point.timestamp);
+
+    let indicators = setting.technical_indicator;
+    Some(Chart {
+        term: section.current_chart_term_key.or(setting.chart_term),
+        time_frame: setting.chart_time_frame,
+        indicators: indicators
+            .chart_over_indicator
+            .into_iter()
+            .chain(indicators.chart_add_indicator)
+            .collect(),
+        points,
+    })
+}
@@ -0,0 +1,114 @@
+//! Cross-checks of the two ways a quote is read from a page: the embedded
+//! state and the DOM. A disagreement points at a stale state or at selectors
+//! that have drifted from Yahoo's markup.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::normalize::to_ascii;
+use crate::{extract_preloaded_state, parse_decimal, process_dom_data, process_json_data, DataSource, Format, OutputOptions, Symbol};
+
+/// Fields compared between the two sources.
+const COMPARED_KEYS: &[&str] = &["name", "price", "price_change", "price_change_rate"];
+
+/// The outcome of comparing the JSON and DOM readings of one page.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Consistency {
+    /// Both sources were read and agree on every compared field.
+    pub consistent: bool,
+    /// False when either source could not be read, so nothing was compared.
+    pub compared: bool,
+    pub mismatches: Vec<Mismatch>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub json_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dom_error: Option<String>,
+}
+
+/// A field the two sources read differently, with the values as displayed.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    pub field: &'static str,
+    pub json: Option<String>,
+    pub dom: Option<String>,
+}
+
+/// Reads the quote of `code` from both the embedded state and the DOM of
+/// `body` and compares name, price, change and rate.
+///
+/// Numbers are compared as decimals, so `"2,018"` matches `"2018"` and
+/// `"+0.16"` matches `"+0.16%"`. Names are compared without spaces and with
+/// full-width letters folded, and match when one contains the other: page
+/// headings often add a suffix such as `の指数情報・推移`.
+pub fn verify_sources(
+    code: &str,
+    symbol: &Symbol,
+    body: &str,
+    data_sources: &[DataSource],
+    fetched_at: DateTime<Utc>,
+) -> Consistency {
+    let output = OutputOptions {
+        keys: Some(COMPARED_KEYS.iter().map(|key| key.to_string()).collect()),
+        format: Format::Raw,
+        fetched_at,
+        usd_jpy: None,
+    };
+    let json = extract_preloaded_state(body)
+        .map_err(|e| e.to_string())
+        .and_then(|state| process_json_data(code, symbol, &state, data_sources, &output).map_err(|e| e.to_string()));
+    let dom = process_dom_data(code, body, &output).map_err(|e| e.to_string());
+
+    let (json, dom) = match (json, dom) {
+        (Ok(json), Ok(dom)) => (json, dom),
+        (json, dom) => {
+            return Consistency {
+                consistent: false,
+                compared: false,
+                mismatches: Vec::new(),
+                json_error: json.err(),
+                dom_error: dom.err(),
+            }
+        }
+    };
+
+    let mismatches: Vec<Mismatch> = COMPARED_KEYS
+        .iter()
+        .filter_map(|&field| {
+            let json = text(&json, field);
+            let dom = text(&dom, field);
+            let agree = match (&json, &dom) {
+                (Some(json), Some(dom)) if field == "name" => same_name(json, dom),
+                (Some(json), Some(dom)) => same_number(json, dom),
+                (None, None) => true,
+                _ => false,
+            };
+            (!agree).then_some(Mismatch { field, json, dom })
+        })
+        .collect();
+
+    Consistency {
+        consistent: mismatches.is_empty(),
+        compared: true,
+        mismatches,
+        json_error: None,
+        dom_error: None,
+    }
+}
+
+fn text(fields: &Map<String, Value>, key: &str) -> Option<String> {
+    fields.get(key).and_then(Value::as_str).map(str::to_string)
+}
+
+fn same_number(json: &str, dom: &str) -> bool {
+    match (parse_decimal(json), parse_decimal(dom)) {
+        (Some(json), Some(dom)) => json == dom,
+        _ => json.trim() == dom.trim(),
+    }
+}
+
+fn same_name(json: &str, dom: &str) -> bool {
+    let fold = |name: &str| -> String { name.chars().map(to_ascii).filter(|c| !c.is_whitespace()).collect() };
+    let (json, dom) = (fold(json), fold(dom));
+    !json.is_empty() && !dom.is_empty() && (json.contains(&dom) || dom.contains(&json))
+}
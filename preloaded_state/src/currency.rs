@@ -0,0 +1,75 @@
+//! US-listed prices: pure US tickers and the `priceBoard.usStock` listing of
+//! dual-listed Japanese stocks, converted to yen with the `USDJPY=X` board.
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{parse_decimal, CurrencyPrice, PriceBoard, Quote, Symbol};
+
+/// The US side of a quote.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct UsQuote {
+    /// Currency of the page's own `price`: `"USD"` for US tickers, `"JPY"`
+    /// for Japanese stocks.
+    pub currency: &'static str,
+    /// The US price: the page price of a US ticker, or the price of a
+    /// Japanese stock's US listing (often an ADR, so not per share).
+    pub usd_price: Option<Decimal>,
+    pub usd_price_raw: Option<String>,
+    /// `usd_price` in yen at `usd_jpy`, rounded to 2 decimal places.
+    pub jpy_price: Option<Decimal>,
+    /// The `USDJPY=X` bid used for `jpy_price`.
+    pub usd_jpy: Option<Decimal>,
+    pub us_market: Option<String>,
+    pub us_link: Option<String>,
+}
+
+impl UsQuote {
+    /// Fills `jpy_price` from a USD/JPY rate.
+    pub fn convert(&mut self, usd_jpy: Decimal) {
+        self.usd_jpy = Some(usd_jpy);
+        self.jpy_price = self.usd_price.map(|usd| (usd * usd_jpy).round_dp(2));
+    }
+}
+
+/// Builds the US side of `quote`, or `None` for a Japanese code whose page
+/// has no `usStock` listing.
+pub fn extract_us_quote(state: &Value, symbol: &Symbol, quote: &Quote) -> Option<UsQuote> {
+    let board = state
+        .pointer("/mainStocksPriceBoard/priceBoard")
+        .and_then(|b| PriceBoard::deserialize(b).ok())
+        .unwrap_or_default();
+
+    if let Symbol::UsStock(_) = symbol {
+        return Some(UsQuote {
+            currency: "USD",
+            usd_price: quote.price.as_deref().and_then(parse_decimal),
+            usd_price_raw: quote.price.clone(),
+            jpy_price: None,
+            usd_jpy: None,
+            us_market: board.market_name,
+            us_link: Some(symbol.quote_url()),
+        });
+    }
+
+    let listing = board.us_stock?;
+    Some(UsQuote {
+        currency: "JPY",
+        usd_price: listing.us_price.as_deref().and_then(parse_decimal),
+        usd_price_raw: listing.us_price,
+        jpy_price: None,
+        usd_jpy: None,
+        us_market: listing.us_market_name,
+        us_link: listing.us_link,
+    })
+}
+
+/// Reads the USD/JPY bid from the currency board of a `USDJPY=X` page.
+pub fn extract_usd_jpy(state: &Value) -> Option<Decimal> {
+    let board = CurrencyPrice::deserialize(state.pointer("/mainCurrencyPriceBoard/currencyPrices")?).ok()?;
+    if board.currency_pair_code.as_deref() != Some("USDJPY") {
+        return None;
+    }
+    board.bid.as_deref().and_then(parse_decimal)
+}
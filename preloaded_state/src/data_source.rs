@@ -0,0 +1,151 @@
+//! Locations in `__PRELOADED_STATE__` that hold quote data, and how the
+//! object found at each one is decoded into a [`Quote`].
+//!
+//! The worker loads the list from its bundled `data_sources.toml` or a
+//! runtime override, through [`parse_data_sources`].
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{CurrencyPrice, FundPrice, IndexPrice, PriceBoard, Quote, RegistryError};
+
+/// Output keys a `mapped` board can fill, matching the fields of `Quote`.
+const QUOTE_KEYS: &[&str] = &[
+    "code",
+    "name",
+    "price",
+    "price_change",
+    "price_change_rate",
+    "dividend_yield",
+    "update_time",
+];
+
+/// A registry document: `[[source]]` tables in TOML, or a `"source"` array in JSON.
+#[derive(Serialize, Deserialize)]
+struct Registry {
+    source: Vec<DataSource>,
+}
+
+/// Defines a known location for financial data within the __PRELOADED_STATE__ JSON.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DataSource {
+    pub name: String,
+    /// Dot-separated path to the board object, e.g. `mainStocksPriceBoard.priceBoard`.
+    pub path: String,
+    pub board: Board,
+    /// Output key -> JSON key, used by `mapped` boards.
+    #[serde(default)]
+    pub mappings: HashMap<String, String>,
+}
+
+/// The model used to decode the object found at a `DataSource` path.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Board {
+    Stock,
+    Currency,
+    Index,
+    Fund,
+    /// No typed model: fields are read through the source's `mappings`.
+    Mapped,
+}
+
+impl Board {
+    /// Decodes `obj` with the board's typed model. `Mapped` boards need their
+    /// mappings and are decoded through `DataSource::decode` instead.
+    pub fn decode(self, obj: &Map<String, Value>) -> Option<Quote> {
+        let value = Value::Object(obj.clone());
+        match self {
+            Board::Stock => serde_json::from_value::<PriceBoard>(value).ok().map(Quote::from),
+            Board::Currency => serde_json::from_value::<CurrencyPrice>(value).ok().map(Quote::from),
+            Board::Index => serde_json::from_value::<IndexPrice>(value).ok().map(Quote::from),
+            Board::Fund => serde_json::from_value::<FundPrice>(value).ok().map(Quote::from),
+            Board::Mapped => None,
+        }
+    }
+}
+
+impl DataSource {
+    pub fn path_segments(&self) -> Vec<&str> {
+        self.path.split('.').collect()
+    }
+
+    pub fn decode(&self, obj: &Map<String, Value>) -> Option<Quote> {
+        if self.board != Board::Mapped {
+            return self.board.decode(obj);
+        }
+        let field = |key: &str| {
+            let value = obj.get(self.mappings.get(key)?)?;
+            match value {
+                Value::String(s) => Some(s.clone()),
+                Value::Number(_) | Value::Bool(_) => Some(value.to_string()),
+                _ => None,
+            }
+        };
+        Some(Quote {
+            code: field("code")?,
+            name: field("name"),
+            price: field("price"),
+            price_change: field("price_change"),
+            price_change_rate: field("price_change_rate"),
+            dividend_yield: field("dividend_yield"),
+            update_time: field("update_time"),
+        })
+    }
+}
+
+/// Parses a TOML or JSON registry document and validates every source.
+pub fn parse_data_sources(text: &str) -> Result<Vec<DataSource>, RegistryError> {
+    let registry: Registry = if text.trim_start().starts_with('{') {
+        serde_json::from_str(text).map_err(|e| RegistryError::Syntax(e.to_string()))?
+    } else {
+        toml::from_str(text).map_err(|e| RegistryError::Syntax(e.to_string()))?
+    };
+
+    let problems = validate_data_sources(&registry.source);
+    if !problems.is_empty() {
+        return Err(RegistryError::Invalid(problems));
+    }
+    Ok(registry.source)
+}
+
+/// Describes every problem that would make a source unusable; empty when all
+/// are fine.
+pub fn validate_data_sources(sources: &[DataSource]) -> Vec<String> {
+    let mut problems = Vec::new();
+    if sources.is_empty() {
+        problems.push("no sources defined".to_string());
+    }
+
+    let mut seen_names = Vec::new();
+    for source in sources {
+        let name = &source.name;
+        if seen_names.contains(&name) {
+            problems.push(format!("{}: duplicate name", name));
+        }
+        seen_names.push(name);
+
+        if source.path_segments().iter().any(|s| s.is_empty() || s.contains(char::is_whitespace)) {
+            problems.push(format!("{}: bad path '{}'", name, source.path));
+        }
+
+        if source.board == Board::Mapped {
+            if !source.mappings.contains_key("code") {
+                problems.push(format!("{}: missing `code` mapping", name));
+            }
+            for (key, json_key) in &source.mappings {
+                if !QUOTE_KEYS.contains(&key.as_str()) {
+                    problems.push(format!("{}: unknown mapping key '{}'", name, key));
+                }
+                if json_key.is_empty() {
+                    problems.push(format!("{}: empty JSON key for mapping '{}'", name, key));
+                }
+            }
+        } else if !source.mappings.is_empty() {
+            problems.push(format!("{}: mappings are only used by mapped boards", name));
+        }
+    }
+    problems
+}
@@ -0,0 +1,158 @@
+//! Typed view of `mainStocksDetail`: the day's trading summary (`detail`) and
+//! the reference indicators (`referenceIndex`) shown under a stock's price board.
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::board::lenient_string;
+use crate::parse_decimal;
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct MainStocksDetail {
+    #[serde(default)]
+    detail: DetailSection,
+    #[serde(default)]
+    reference_index: ReferenceIndex,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct DetailSection {
+    #[serde(deserialize_with = "lenient_string")]
+    previous_price: Option<String>,
+    previous_price_date: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    open_price: Option<String>,
+    open_price_date_time: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    high_price: Option<String>,
+    high_price_date_time: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    low_price: Option<String>,
+    low_price_date_time: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    volume: Option<String>,
+    volume_date_time: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    trading_value: Option<String>,
+    trading_value_date_time: Option<String>,
+    price_limit: Option<String>,
+    price_limit_date: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct ReferenceIndex {
+    #[serde(deserialize_with = "lenient_string")]
+    per: Option<String>,
+    per_prefix: Option<String>,
+    per_date_time: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    pbr: Option<String>,
+    pbr_prefix: Option<String>,
+    pbr_date_time: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    eps: Option<String>,
+    eps_prefix: Option<String>,
+    eps_date: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    bps: Option<String>,
+    bps_prefix: Option<String>,
+    bps_date: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    shares_issued: Option<String>,
+    shares_issued_date: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    share_unit: Option<String>,
+}
+
+/// A number from the detail section with the stamp shown next to it.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct DetailValue {
+    pub value: Option<Decimal>,
+    pub raw: Option<String>,
+    /// The time, date or fiscal period the page shows for the value
+    /// (`"09:03"`, `"11/04"`, `"2026/03"`).
+    pub as_of: Option<String>,
+    /// Basis marker such as `"(連)"` for consolidated figures.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub basis: Option<String>,
+}
+
+impl DetailValue {
+    fn new(raw: Option<String>, as_of: Option<String>) -> Self {
+        DetailValue {
+            value: raw.as_deref().and_then(parse_decimal),
+            raw,
+            as_of,
+            basis: None,
+        }
+    }
+
+    fn with_basis(mut self, basis: Option<String>) -> Self {
+        self.basis = basis.filter(|b| !b.is_empty());
+        self
+    }
+}
+
+/// The daily price limit (`"1,595～2,595"`).
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct PriceLimit {
+    pub lower: Option<Decimal>,
+    pub upper: Option<Decimal>,
+    pub raw: Option<String>,
+    pub as_of: Option<String>,
+}
+
+impl PriceLimit {
+    fn new(raw: Option<String>, as_of: Option<String>) -> Self {
+        let (lower, upper) = raw
+            .as_deref()
+            .and_then(|r| r.split_once(['～', '~']))
+            .map(|(lo, hi)| (parse_decimal(lo), parse_decimal(hi)))
+            .unwrap_or_default();
+        PriceLimit { lower, upper, raw, as_of }
+    }
+}
+
+/// Trading summary and fundamentals of a stock, from `mainStocksDetail`.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct StockDetail {
+    pub previous_price: DetailValue,
+    pub open_price: DetailValue,
+    pub high_price: DetailValue,
+    pub low_price: DetailValue,
+    pub volume: DetailValue,
+    pub trading_value: DetailValue,
+    pub price_limit: PriceLimit,
+    pub per: DetailValue,
+    pub pbr: DetailValue,
+    pub eps: DetailValue,
+    pub bps: DetailValue,
+    pub shares_issued: DetailValue,
+    pub share_unit: DetailValue,
+}
+
+/// Extracts the stock detail section, or `None` when the page has none
+/// (index, currency and fund pages).
+pub fn extract_stock_detail(state: &Value) -> Option<StockDetail> {
+    let section = state.get("mainStocksDetail")?;
+    let MainStocksDetail { detail: d, reference_index: r } = MainStocksDetail::deserialize(section).ok()?;
+    Some(StockDetail {
+        previous_price: DetailValue::new(d.previous_price, d.previous_price_date),
+        open_price: DetailValue::new(d.open_price, d.open_price_date_time),
+        high_price: DetailValue::new(d.high_price, d.high_price_date_time),
+        low_price: DetailValue::new(d.low_price, d.low_price_date_time),
+        volume: DetailValue::new(d.volume, d.volume_date_time),
+        trading_value: DetailValue::new(d.trading_value, d.trading_value_date_time),
+        price_limit: PriceLimit::new(d.price_limit, d.price_limit_date),
+        per: DetailValue::new(r.per, r.per_date_time).with_basis(r.per_prefix),
+        pbr: DetailValue::new(r.pbr, r.pbr_date_time).with_basis(r.pbr_prefix),
+        eps: DetailValue::new(r.eps, r.eps_date).with_basis(r.eps_prefix),
+        bps: DetailValue::new(r.bps, r.bps_date).with_basis(r.bps_prefix),
+        shares_issued: DetailValue::new(r.shares_issued, r.shares_issued_date),
+        share_unit: DetailValue::new(r.share_unit, None),
+    })
+}
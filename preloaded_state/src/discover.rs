@@ -0,0 +1,560 @@
+//! Discovery of the values a quote page shows, for selector generation: for
+//! each field, the texts that look like it, ranked by how likely they are.
+//!
+//! Pages are fetched through a [`PageFetcher`] paced by a [`FetchScheduler`],
+//! so discovery runs against the network, fixtures or a replay alike.
+
+use std::collections::HashMap;
+
+use scraper::{Html, Selector};
+use serde::Serialize;
+
+use crate::{extract_preloaded_state, Clock, FetchScheduler, PageFetcher, Symbol, UpstreamError};
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RankedCandidate {
+    pub text: String,
+    pub score: u32,
+    pub reason: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DiscoveredData {
+    pub code: String,
+    pub url: String,
+    /// The page the candidates were found in, for selector generation.
+    #[serde(skip)]
+    pub html: String,
+    pub name_candidates: Vec<RankedCandidate>,
+    pub price_candidates: Vec<RankedCandidate>,
+    pub change_abs_candidates: Vec<RankedCandidate>,
+    pub change_pct_candidates: Vec<RankedCandidate>,
+    pub update_time_candidates: Vec<RankedCandidate>,
+}
+
+/// Fetches the page of `symbol` and ranks the candidates for each field, with
+/// the strategy for its kind of page. `code` is reported as given.
+///
+/// A currency pair reads its rate and update time from the `=X` page and its
+/// change from the `=FX` page, whichever of the two was asked for.
+pub async fn discover_symbol<C: Clock, F: PageFetcher>(
+    scheduler: &FetchScheduler<C>,
+    fetcher: &F,
+    code: &str,
+    symbol: &Symbol,
+) -> Result<DiscoveredData, UpstreamError> {
+    let page = |symbol: Symbol| async move {
+        let url = symbol.quote_url();
+        let html = scheduler.fetch(fetcher, &url).await?;
+        Ok::<_, UpstreamError>((url, html))
+    };
+    match symbol {
+        Symbol::Index(_) => {
+            let (url, html) = page(symbol.clone()).await?;
+            Ok(discover_index(code, url, html))
+        }
+        Symbol::CurrencyX(pair) | Symbol::CurrencyFx(pair) => {
+            let (url, html) = page(Symbol::CurrencyX(pair.clone())).await?;
+            let data_x = discover_currency_x(code, url, html);
+            let (url, html) = page(Symbol::CurrencyFx(pair.clone())).await?;
+            let data_fx = discover_currency_fx(code, url, html);
+            Ok(DiscoveredData {
+                url: data_x.url, // Use the URL and page of the primary (=X) page
+                html: data_x.html,
+                change_abs_candidates: data_fx.change_abs_candidates,
+                change_pct_candidates: data_fx.change_pct_candidates,
+                ..data_x
+            })
+        }
+        _ => {
+            let (url, html) = page(symbol.clone()).await?;
+            Ok(discover_stock(code, url, html))
+        }
+    }
+}
+
+fn deduplicate_and_sort_candidates(candidates: Vec<RankedCandidate>) -> Vec<RankedCandidate> {
+    let mut map: HashMap<String, RankedCandidate> = HashMap::new();
+    for candidate in candidates {
+        map.entry(candidate.text.clone())
+            .and_modify(|e| {
+                if candidate.score > e.score {
+                    *e = candidate.clone();
+                }
+            })
+            .or_insert(candidate);
+    }
+    let mut final_candidates: Vec<RankedCandidate> = map.into_values().collect();
+    final_candidates.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.text.cmp(&b.text)));
+    final_candidates
+}
+
+fn discover_stock(code: &str, url: String, html: String) -> DiscoveredData {
+    let document = Html::parse_document(&html);
+
+    let mut name_candidates: Vec<RankedCandidate> = Vec::new();
+    let mut price_candidates: Vec<RankedCandidate> = Vec::new();
+    let mut change_abs_candidates: Vec<RankedCandidate> = Vec::new();
+    let mut change_pct_candidates: Vec<RankedCandidate> = Vec::new();
+    let mut update_time_candidates: Vec<RankedCandidate> = Vec::new();
+
+    let mut base_name = String::new();
+    if let Ok(title_selector) = Selector::parse("title") {
+        if let Some(title_el) = document.select(&title_selector).next() {
+            let title_text = title_el.text().collect::<String>();
+            base_name = title_text.split('【').next().unwrap_or("")
+                .split('(').next().unwrap_or("")
+                .split('：').next().unwrap_or("")
+                .trim().to_string();
+            if !base_name.is_empty() {
+                name_candidates.push(RankedCandidate { text: title_text.clone(), score: 50, reason: "Original <title> text".to_string() });
+            }
+        }
+    }
+    if !base_name.is_empty() {
+        // Safely parse the heading selector; fall back to simpler selectors if parsing fails.
+        let heading_selectors = match Selector::parse("h1, h2") {
+            Ok(sel) => sel,
+            Err(_) => {
+                match Selector::parse("h1") {
+                    Ok(s) => s,
+                    Err(_) => {
+                        // '*' should always be a valid selector; unwrap is safe here
+                        Selector::parse("*").unwrap()
+                    }
+                }
+            }
+        };
+
+        for element in document.select(&heading_selectors) {
+            let text = element.text().collect::<String>().trim().to_string();
+            if text.is_empty() { continue; }
+            if text == base_name {
+                name_candidates.push(RankedCandidate { text, score: 110, reason: format!("Exact match in <{}>", element.value().name()) });
+            } else if text.contains(&base_name) {
+                name_candidates.push(RankedCandidate { text, score: 100, reason: format!("Contains base name in <{}>", element.value().name()) });
+            }
+        }
+    }
+
+    // より広いセレクターパターンを試す
+    for selector_str in &[
+        "span[class*='PriceBoard__price'] span[class*='StyledNumber__value']", // Add this with high priority
+        "[class*='price'], [class*='Price']",
+        "span[class*='value'], div[class*='value']",
+        "[class*='board'] span, [class*='Board'] span",
+        "[data-field='regularMarketPrice']",
+        "[class*='quote'], [class*='Quote']",
+        "span[class*='last'], div[class*='last']",
+        "[class*='current'], [class*='Current']"
+    ] {
+        if let Ok(sel) = Selector::parse(selector_str) {
+            for element in document.select(&sel) {
+                let text = element.text().collect::<String>().trim().to_string();
+                // 数値っぽい文字列かどうかをチェック（より緩やかな判定）
+                if text.chars().any(|c| c.is_ascii_digit()) {
+                    let cleaned_text = text.replace(",", "");
+                    if let Ok(parsed_price) = cleaned_text.parse::<f64>() {
+                        if parsed_price >= 0.0 {
+                            let mut score = 50;
+                            let class_attr = element.value().attr("class").unwrap_or("");
+                            // Assign higher score for the new specific selector
+                            if *selector_str == "span[class*='PriceBoard__price'] span[class*='StyledNumber__value']" {
+                                score += 100; // High score for current price
+                            }
+                            if text.contains(',') { score += 30; }
+                            if class_attr.contains("value") { score += 20; }
+                            if class_attr.contains("large") { score += 10; }
+                            if class_attr.contains("code") || class_attr.contains("symbol") { score -= 40; }
+                            price_candidates.push(RankedCandidate {
+                                text: text.clone(),
+                                score,
+                                reason: format!("Found in element with class: {} (selector: {})", class_attr, selector_str)
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+    // 候補が見つからなかった場合のフォールバック
+    if price_candidates.is_empty() {
+        // フォールバック: より広いセレクターで数値を探す
+        if let Ok(sel) = Selector::parse("span, div") {
+            for element in document.select(&sel) {
+                let text = element.text().collect::<String>().trim().to_string();
+                if text.chars().any(|c| c.is_ascii_digit()) {
+                    let cleaned_text = text.replace(",", "");
+                    if let Ok(parsed_price) = cleaned_text.parse::<f64>() {
+                        if parsed_price >= 0.0 {
+                            price_candidates.push(RankedCandidate { 
+                                text, 
+                                score: 10, // フォールバックなので低いスコア
+                                reason: format!("Fallback: found number in {}", element.value().name()) 
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if let Ok(sel) = Selector::parse("[class*='PriceChangeLabel__primary']") {
+        for element in document.select(&sel) {
+            let text = element.text().collect::<String>().trim().to_string();
+            if (text.starts_with('+') || text.starts_with('-')) && text.chars().any(|c| c.is_ascii_digit()) {
+                change_abs_candidates.push(RankedCandidate { text: text.clone(), score: 100, reason: "Found in primary change label".to_string() });
+            }
+            if text.contains('%') && text.contains('(') {
+                change_pct_candidates.push(RankedCandidate { text, score: 100, reason: "Found in secondary change label".to_string() });
+            }
+        }
+    }
+
+    // Fallback for change_pct if not found by the primary selector
+    if change_pct_candidates.is_empty() {
+        for selector_str in &[
+            "[class*='change']", // Look for classes containing 'change'
+            "[class*='percent']", // Look for classes containing 'percent'
+            "span",               // General span elements
+            "div",                // General div elements
+        ] {
+            if let Ok(sel) = Selector::parse(selector_str) {
+                for element in document.select(&sel) {
+                    let text = element.text().collect::<String>().trim().to_string();
+                    if text.contains('%') && (text.starts_with('+') || text.starts_with('-') || text.chars().any(|c| c.is_ascii_digit())) {
+                        change_pct_candidates.push(RankedCandidate {
+                            text: text.clone(),
+                            score: 50, // Lower score for broader fallback
+                            reason: format!("Broader fallback: found '%' in element with selector: {}", selector_str)
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    // Update Time _CommonPriceBoard__time_1g7gt_55
+    for selector_str in &["ul[class*='PriceBoard__times'] time", "time[class*='timestamp']"] {
+        if let Ok(sel) = Selector::parse(selector_str) {
+            for element in document.select(&sel) {
+                let text = element.text().collect::<String>().trim().to_string();
+                if !text.is_empty() {
+                    update_time_candidates.push(RankedCandidate { text, score: 100, reason: format!("Found in time element with selector: {}", selector_str) });
+                }
+            }
+        }
+    }
+
+    let final_name_candidates = deduplicate_and_sort_candidates(name_candidates);
+    let final_price_candidates = deduplicate_and_sort_candidates(price_candidates);
+    let final_change_abs_candidates = deduplicate_and_sort_candidates(change_abs_candidates);
+    let final_change_pct_candidates = deduplicate_and_sort_candidates(change_pct_candidates);
+    let final_update_time_candidates = deduplicate_and_sort_candidates(update_time_candidates);
+
+    DiscoveredData {
+        code: code.to_string(),
+        url,
+        html,
+        name_candidates: final_name_candidates,
+        price_candidates: final_price_candidates,
+        change_abs_candidates: final_change_abs_candidates,
+        change_pct_candidates: final_change_pct_candidates,
+        update_time_candidates: final_update_time_candidates,
+    }
+}
+
+fn discover_index(code: &str, url: String, html: String) -> DiscoveredData {
+    let document = Html::parse_document(&html);
+
+    let mut name_candidates: Vec<RankedCandidate> = Vec::new();
+    let mut price_candidates: Vec<RankedCandidate> = Vec::new();
+    let mut change_abs_candidates: Vec<RankedCandidate> = Vec::new();
+    let mut change_pct_candidates: Vec<RankedCandidate> = Vec::new();
+    let mut update_time_candidates: Vec<RankedCandidate> = Vec::new();
+
+    // Try to extract data from window.__PRELOADED_STATE__ JSON
+    if let Ok(parsed_json) = extract_preloaded_state(&html) {
+        // Extract Name
+        if let Some(name_val) = parsed_json["pageInfo"]["title"].as_str() {
+            let cleaned_name = name_val.split(" - ").next().unwrap_or("").trim().to_string();
+            if !cleaned_name.is_empty() {
+                name_candidates.push(RankedCandidate { text: cleaned_name.clone(), score: 100, reason: "Found in __PRELOADED_STATE__ (title)".to_string() });
+            }
+        }
+
+        // Extract Price, Change, and Pct from priceBoard
+        if let Some(price_board) = parsed_json.get("priceBoard") {
+            // Price
+            if let Some(price_val) = price_board.get("price").and_then(|v| v.as_str()) {
+                price_candidates.push(RankedCandidate { text: price_val.to_string(), score: 100, reason: "Found in __PRELOADED_STATE__ (price)".to_string() });
+            }
+            // Change Absolute
+            if let Some(change_val) = price_board.get("change").and_then(|v| v.as_str()) {
+                if !change_val.is_empty() {
+                    change_abs_candidates.push(RankedCandidate {
+                        text: change_val.to_string(),
+                        score: 100,
+                        reason: "Found in __PRELOADED_STATE__ (change)".to_string(),
+                    });
+                }
+            }
+            // Change Percentage
+            if let Some(change_pct_val) = price_board.get("changePercent").and_then(|v| v.as_str()) {
+                if !change_pct_val.is_empty() {
+                    let cleaned_pct = change_pct_val.trim_matches(|c| c == '(' || c == ')').to_string();
+                    change_pct_candidates.push(RankedCandidate {
+                        text: cleaned_pct.clone(),
+                        score: 100,
+                        reason: "Found in __PRELOADED_STATE__ (changePercent)".to_string(),
+                    });
+                }
+            }
+            // Update Time
+            if let Some(time_val) = price_board.get("marketTime").or(price_board.get("tradeTime")).and_then(|v| v.as_str()) {
+                if !time_val.is_empty() {
+                    update_time_candidates.push(RankedCandidate { text: time_val.to_string(), score: 100, reason: "Found in __PRELOADED_STATE__ (marketTime/tradeTime)".to_string() });
+                }
+            }
+        }
+    }
+
+    // Fallback for Name if JSON extraction fails
+    if name_candidates.is_empty() {
+        // Use title tag as a primary fallback
+        if let Ok(sel) = Selector::parse("title") {
+            if let Some(el) = document.select(&sel).next() {
+                let title_text = el.text().collect::<String>();
+                let cleaned_name = title_text.split(" - ").next().unwrap_or("").trim().to_string();
+                 if !cleaned_name.is_empty() {
+                    name_candidates.push(RankedCandidate { text: cleaned_name, score: 80, reason: "Found in <title> tag (fallback)".to_string() });
+                }
+            }
+        }
+        // Use h1 tag as a secondary fallback
+        if name_candidates.is_empty() {
+             if let Ok(sel) = Selector::parse("h1") {
+                if let Some(el) = document.select(&sel).next() {
+                    let h1_text = el.text().collect::<String>().trim().to_string();
+                    if !h1_text.is_empty() {
+                        name_candidates.push(RankedCandidate { text: h1_text, score: 70, reason: "Found in <h1> tag (fallback)".to_string() });
+                    }
+                }
+            }
+        }
+    }
+
+    // Fallback to DOM scraping for price, change_abs, change_pct if JSON extraction fails or is incomplete
+    if price_candidates.is_empty() || change_abs_candidates.is_empty() || change_pct_candidates.is_empty() {
+        // Price
+        if price_candidates.is_empty() {
+            if let Ok(sel) = Selector::parse("div[class*='_CommonPriceBoard__priceBlock'] span[class*='_StyledNumber__value']") {
+                for element in document.select(&sel) {
+                    let text = element.text().collect::<String>().trim().to_string();
+                    if !text.starts_with('+') && !text.starts_with('-') {
+                        if let Ok(parsed_price) = text.replace(",", "").parse::<f64>() {
+                            if parsed_price >= 0.0 {
+                                price_candidates.push(RankedCandidate { text: text.clone(), score: 90, reason: "Found in _CommonPriceBoard__priceBlock (fallback)".to_string() });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // Broader fallback for price within the main price information block
+        if price_candidates.is_empty() {
+            if let Ok(sel) = Selector::parse("div[class*='_BasePriceBoard__priceInformation'] span, div[class*='_BasePriceBoard__priceInformation'] div") {
+                for element in document.select(&sel) {
+                    let text = element.text().collect::<String>().trim().to_string();
+                    // Heuristic to distinguish price from change values
+                    if text.chars().any(|c| c.is_ascii_digit()) && !text.starts_with('+') && !text.starts_with('-') && !text.contains('%') {
+                        let cleaned_text = text.replace(",", "");
+                        if let Ok(parsed_price) = cleaned_text.parse::<f64>() {
+                            if parsed_price >= 0.0 {
+                                price_candidates.push(RankedCandidate { 
+                                    text: text.clone(), 
+                                    score: 70, // Lower score for broader fallback
+                                    reason: format!("Broader fallback in _BasePriceBoard__priceInformation: {}", element.value().name()) 
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // Change Absolute
+        if change_abs_candidates.is_empty() {
+            if let Ok(sel) = Selector::parse("span[class*='_PriceChangeLabel__primary'] span[class*='_StyledNumber__value']") {
+                for element in document.select(&sel) {
+                    let text = element.text().collect::<String>().trim().to_string();
+                    if text.starts_with('+') || text.starts_with('-') {
+                        change_abs_candidates.push(RankedCandidate { text: text.clone(), score: 90, reason: "Found in _PriceChangeLabel__primary (fallback)".to_string() });
+                    }
+                }
+            }
+        }
+
+        // Change Percentage
+        if change_pct_candidates.is_empty() {
+            if let Ok(sel) = Selector::parse("span[class*='_PriceChangeLabel__secondary'] span[class*='_StyledNumber__value']") {
+                for element in document.select(&sel) {
+                    let text = element.text().collect::<String>().trim().to_string();
+                    if !text.is_empty() {
+                        change_pct_candidates.push(RankedCandidate { text: text.clone(), score: 90, reason: "Found in _PriceChangeLabel__secondary (fallback)".to_string() });
+                    }
+                }
+            }
+        }
+
+        // Update Time
+        if update_time_candidates.is_empty() {
+            if let Ok(sel) = Selector::parse("span[class*='_Time'], time[class*='timestamp']") {
+                for element in document.select(&sel) {
+                    let text = element.text().collect::<String>().trim().to_string();
+                    if !text.is_empty() {
+                        update_time_candidates.push(RankedCandidate { text: text.clone(), score: 90, reason: "Found in DOM (fallback)".to_string() });
+                    }
+                }
+            }
+        }
+    }
+
+    let final_name_candidates = deduplicate_and_sort_candidates(name_candidates);
+    let final_price_candidates = deduplicate_and_sort_candidates(price_candidates);
+    let final_change_abs_candidates = deduplicate_and_sort_candidates(change_abs_candidates);
+    let final_change_pct_candidates = deduplicate_and_sort_candidates(change_pct_candidates);
+    let final_update_time_candidates = deduplicate_and_sort_candidates(update_time_candidates);
+
+    DiscoveredData {
+        code: code.to_string(),
+        url,
+        html,
+        name_candidates: final_name_candidates,
+        price_candidates: final_price_candidates,
+        change_abs_candidates: final_change_abs_candidates,
+        change_pct_candidates: final_change_pct_candidates,
+        update_time_candidates: final_update_time_candidates
+    }
+}
+
+fn discover_currency_x(code: &str, url: String, html: String) -> DiscoveredData {
+    let document = Html::parse_document(&html);
+
+    let mut name_candidates: Vec<RankedCandidate> = Vec::new();
+    let mut price_candidates: Vec<RankedCandidate> = Vec::new();
+    let mut update_time_candidates: Vec<RankedCandidate> = Vec::new();
+
+    // --- DOM Fallback Logic --- 
+
+    // Name
+    if let Ok(sel) = Selector::parse("h1") {
+        if let Some(el) = document.select(&sel).next() {
+            let text = el.text().collect::<String>();
+            let cleaned_name = text.split(" - ").next().unwrap_or("").trim().to_string();
+            if !cleaned_name.is_empty() {
+                name_candidates.push(RankedCandidate { text: cleaned_name, score: 100, reason: "Found in <h1>".to_string() });
+            }
+        }
+    }
+
+    // Price (Selector Guess)
+    if let Ok(sel) = Selector::parse("div[class*='rate'] span, span[class*='price']") {
+        for element in document.select(&sel) {
+            let text = element.text().collect::<String>().trim().to_string();
+            let cleaned_text = text.replace(",", "");
+            if cleaned_text.parse::<f64>().is_ok() && !text.is_empty() {
+                 let score = 90;
+                 price_candidates.push(RankedCandidate { text: text.clone(), score, reason: "Guessed DOM selector for price".to_string() });
+            }
+        }
+    }
+
+    // Update Time (Selector Guess)
+    if let Ok(sel) = Selector::parse("span[class*='time'], time") {
+        for element in document.select(&sel) {
+            let text = element.text().collect::<String>().trim().to_string();
+            if !text.is_empty() {
+                update_time_candidates.push(RankedCandidate { text, score: 90, reason: "Guessed DOM selector for time".to_string() });
+            }
+        }
+    }
+
+    let final_name_candidates = deduplicate_and_sort_candidates(name_candidates);
+    let final_price_candidates = deduplicate_and_sort_candidates(price_candidates);
+    let final_update_time_candidates = deduplicate_and_sort_candidates(update_time_candidates);
+
+    DiscoveredData {
+        code: code.to_string(),
+        url,
+        html,
+        name_candidates: final_name_candidates,
+        price_candidates: final_price_candidates,
+        change_abs_candidates: vec![], // Not searched in this function
+        change_pct_candidates: vec![], // Not searched in this function
+        update_time_candidates: final_update_time_candidates,
+    }
+}
+
+fn discover_currency_fx(code: &str, url: String, html: String) -> DiscoveredData {
+    let document = Html::parse_document(&html);
+
+    let mut name_candidates: Vec<RankedCandidate> = Vec::new();
+    let mut price_candidates: Vec<RankedCandidate> = Vec::new();
+    let mut change_abs_candidates: Vec<RankedCandidate> = Vec::new();
+    let mut change_pct_candidates: Vec<RankedCandidate> = Vec::new();
+
+    // Name
+    if let Ok(sel) = Selector::parse("h1") {
+        if let Some(el) = document.select(&sel).next() {
+            let text = el.text().collect::<String>();
+            let cleaned_name = text.split(" - ").next().unwrap_or("").trim().to_string();
+            if !cleaned_name.is_empty() {
+                name_candidates.push(RankedCandidate { text: cleaned_name, score: 100, reason: "Found in <h1>".to_string() });
+            }
+        }
+    }
+
+    // Price
+    if let Ok(sel) = Selector::parse("div[class*='rate'] span, span[class*='price']") {
+        for element in document.select(&sel) {
+            let text = element.text().collect::<String>().trim().to_string();
+            let cleaned_text = text.replace(",", "");
+            if cleaned_text.parse::<f64>().is_ok() && !text.is_empty() {
+                 price_candidates.push(RankedCandidate { text, score: 90, reason: "Guessed DOM selector for price".to_string() });
+            }
+        }
+    }
+
+    // Change
+    if let Ok(sel) = Selector::parse("[class*='change'], [class*='diff'], [class*='gain'], [class*='loss'], [class*='up'], [class*='down']") {
+        for element in document.select(&sel) {
+            let text = element.text().collect::<String>().trim().to_string();
+            if text.starts_with('+') || text.starts_with('-') {
+                let score = 90;
+                if text.contains('%') {
+                    change_pct_candidates.push(RankedCandidate { text: text.clone(), score, reason: "Guessed DOM selector for change pct".to_string() });
+                } else {
+                    change_abs_candidates.push(RankedCandidate { text: text.clone(), score, reason: "Guessed DOM selector for change abs".to_string() });
+                }
+            }
+        }
+    }
+
+    let final_name_candidates = deduplicate_and_sort_candidates(name_candidates);
+    let final_price_candidates = deduplicate_and_sort_candidates(price_candidates);
+    let final_change_abs_candidates = deduplicate_and_sort_candidates(change_abs_candidates);
+    let final_change_pct_candidates = deduplicate_and_sort_candidates(change_pct_candidates);
+
+    DiscoveredData {
+        code: code.to_string(),
+        url,
+        html,
+        name_candidates: final_name_candidates,
+        price_candidates: final_price_candidates,
+        change_abs_candidates: final_change_abs_candidates,
+        change_pct_candidates: final_change_pct_candidates,
+        update_time_candidates: vec![], // Not searched in this function
+    }
+}
@@ -0,0 +1,79 @@
+//! Dividend per share, dividend yield and payout ratio from `mainStocksDividend`,
+//! completed from the price board when the dividend section has no figures.
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{parse_decimal, parse_rate, PriceBoard, Rate};
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct MainStocksDividend {
+    latest_dividend: LatestDividend,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct LatestDividend {
+    last_dps_formatted: Option<String>,
+    last_dps_date_formatted: Option<String>,
+    last_share_dividend_yield_formatted: Option<String>,
+    last_share_dividend_yield_date_time_formatted: Option<String>,
+    last_payout_ratio_formatted: Option<String>,
+}
+
+/// Dividend figures of a stock. Each `*_raw` field keeps the string the value
+/// was parsed from.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct Dividend {
+    /// Dividend per share (1株配当).
+    pub dps: Option<Decimal>,
+    pub dps_raw: Option<String>,
+    /// Fiscal period or record date the DPS belongs to (`"2026-03-01"`, `"26/03"`).
+    pub dps_date: Option<String>,
+    pub dividend_yield: Option<Rate>,
+    pub dividend_yield_raw: Option<String>,
+    pub dividend_yield_time: Option<String>,
+    pub payout_ratio: Option<Rate>,
+    pub payout_ratio_raw: Option<String>,
+}
+
+/// Extracts the dividend figures, or `None` when the page has neither a
+/// dividend section nor a stock price board (index and currency pages).
+pub fn extract_dividend(state: &Value) -> Option<Dividend> {
+    let section = state.get("mainStocksDividend");
+    let board = state.pointer("/mainStocksPriceBoard/priceBoard");
+    if section.is_none() && board.is_none() {
+        return None;
+    }
+
+    let section = section
+        .and_then(|s| MainStocksDividend::deserialize(s).ok())
+        .unwrap_or_default();
+    let board = board
+        .and_then(|b| PriceBoard::deserialize(b).ok())
+        .unwrap_or_default();
+    let latest = section.latest_dividend;
+
+    // Placeholders such as "---" and "--/--" carry no digits.
+    let shown = |raw: Option<String>| raw.filter(|s| s.chars().any(|c| c.is_ascii_digit()));
+    let dps_raw = shown(latest.last_dps_formatted).or(shown(board.dps));
+    let dps_date = shown(latest.last_dps_date_formatted).or(shown(board.dps_period));
+    let dividend_yield_raw =
+        shown(latest.last_share_dividend_yield_formatted).or(shown(board.share_dividend_yield));
+    let dividend_yield_time =
+        shown(latest.last_share_dividend_yield_date_time_formatted).or(shown(board.share_dividend_yield_time));
+    let payout_ratio_raw = shown(latest.last_payout_ratio_formatted);
+
+    Some(Dividend {
+        dps: dps_raw.as_deref().and_then(parse_decimal),
+        dps_raw,
+        dps_date,
+        dividend_yield: dividend_yield_raw.as_deref().and_then(parse_rate),
+        dividend_yield_raw,
+        dividend_yield_time,
+        payout_ratio: payout_ratio_raw.as_deref().and_then(parse_rate),
+        payout_ratio_raw,
+    })
+}
@@ -0,0 +1,188 @@
+use std::fmt;
+
+use crate::StateSpan;
+
+/// Errors returned while locating or decoding `window.__PRELOADED_STATE__`.
+///
+/// Offsets are byte positions in the HTML page that was scanned.
+#[derive(Debug)]
+pub enum StateError {
+    /// The page has no `window.__PRELOADED_STATE__ = {` assignment.
+    NotFound,
+    /// The object literal starting at `start` has no matching closing brace.
+    Unterminated { start: usize },
+    /// The object literal at `span` is not valid JSON.
+    Malformed { span: StateSpan, source: serde_json::Error },
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::NotFound => write!(f, "window.__PRELOADED_STATE__ not found"),
+            StateError::Unterminated { start } => {
+                write!(f, "window.__PRELOADED_STATE__ object starting at byte {} is not terminated", start)
+            }
+            StateError::Malformed { span, source } => {
+                write!(f, "Failed to parse JSON at bytes {}..{}: {}", span.start, span.end, source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StateError::Malformed { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Errors returned when a `code` cannot be read as a [`Symbol`](crate::Symbol).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SymbolError {
+    Empty,
+    /// The code ends in a market suffix we do not know (`7203.X`).
+    UnknownMarket { input: String, suffix: String },
+    /// The code does not have the shape of any supported symbol.
+    Malformed { input: String, reason: &'static str },
+}
+
+impl fmt::Display for SymbolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SymbolError::Empty => write!(f, "Code is empty"),
+            SymbolError::UnknownMarket { input, suffix } => {
+                write!(f, "Unknown market suffix '.{}' in code '{}'", suffix, input)
+            }
+            SymbolError::Malformed { input, reason } => write!(f, "Invalid code '{}': {}", input, reason),
+        }
+    }
+}
+
+impl std::error::Error for SymbolError {}
+
+/// Errors returned when a data-source registry cannot be used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    /// The document is neither valid TOML nor valid JSON of the registry's shape.
+    Syntax(String),
+    /// The document parsed, but some sources are unusable; one message each.
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::Syntax(message) => f.write_str(message),
+            RegistryError::Invalid(problems) => f.write_str(&problems.join("; ")),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+/// Why a page could not be fetched or read, classified so callers can tell
+/// failures worth retrying from those that are not.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpstreamError {
+    /// The request did not complete (DNS, connection, timeout, body read).
+    Network(String),
+    /// Yahoo answered with an unexpected status.
+    HttpStatus(u16),
+    /// Yahoo answered 429, with its `Retry-After` in seconds when given.
+    RateLimited { retry_after: Option<u64> },
+    /// Yahoo refused the request or served a captcha instead of the page.
+    Blocked,
+    /// The page has no `window.__PRELOADED_STATE__`.
+    StateMissing,
+    /// The embedded state could not be parsed.
+    StateMalformed(String),
+    /// The state has no price board for the requested code.
+    CodeMismatch(String),
+    /// The DOM fallback could not find the essential fields.
+    DomSelectorsFailed(String),
+}
+
+impl UpstreamError {
+    /// Classifies a response that did not carry a usable page: `None` for a
+    /// 2xx page that is not a captcha.
+    pub fn from_response(status: u16, retry_after: Option<&str>, body: &str) -> Option<Self> {
+        match status {
+            200..=299 if is_captcha(body) => Some(UpstreamError::Blocked),
+            200..=299 => None,
+            429 => Some(UpstreamError::RateLimited {
+                retry_after: retry_after.and_then(|value| value.trim().parse().ok()),
+            }),
+            // Yahoo answers 999 to clients it has blocked
+            403 | 999 => Some(UpstreamError::Blocked),
+            _ => Some(UpstreamError::HttpStatus(status)),
+        }
+    }
+
+    /// A stable snake_case name for API responses: `"rate_limited"`, `"state_missing"`, ...
+    pub fn kind(&self) -> &'static str {
+        match self {
+            UpstreamError::Network(_) => "network",
+            UpstreamError::HttpStatus(_) => "http_status",
+            UpstreamError::RateLimited { .. } => "rate_limited",
+            UpstreamError::Blocked => "blocked",
+            UpstreamError::StateMissing => "state_missing",
+            UpstreamError::StateMalformed(_) => "state_malformed",
+            UpstreamError::CodeMismatch(_) => "code_mismatch",
+            UpstreamError::DomSelectorsFailed(_) => "dom_selectors_failed",
+        }
+    }
+
+    /// Whether the same request may succeed later: network failures, 5xx
+    /// statuses and rate limiting. Retrying a block only prolongs it.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            UpstreamError::Network(_) | UpstreamError::RateLimited { .. } => true,
+            UpstreamError::HttpStatus(status) => *status >= 500,
+            _ => false,
+        }
+    }
+
+    /// The wait Yahoo asked for before the next request, in milliseconds.
+    pub fn retry_after_millis(&self) -> Option<u64> {
+        match self {
+            UpstreamError::RateLimited { retry_after } => retry_after.map(|seconds| seconds.saturating_mul(1000)),
+            _ => None,
+        }
+    }
+}
+
+/// A page without the embedded state that mentions a captcha.
+fn is_captcha(body: &str) -> bool {
+    !body.contains("__PRELOADED_STATE__") && body.to_ascii_lowercase().contains("captcha")
+}
+
+impl fmt::Display for UpstreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpstreamError::Network(message) => write!(f, "{}", message),
+            UpstreamError::HttpStatus(status) => write!(f, "Upstream answered HTTP {}", status),
+            UpstreamError::RateLimited { retry_after: Some(seconds) } => {
+                write!(f, "Rate limited by upstream; retry after {} s", seconds)
+            }
+            UpstreamError::RateLimited { retry_after: None } => write!(f, "Rate limited by upstream"),
+            UpstreamError::Blocked => write!(f, "Request blocked by upstream"),
+            UpstreamError::StateMissing => write!(f, "window.__PRELOADED_STATE__ not found"),
+            UpstreamError::StateMalformed(message) => write!(f, "{}", message),
+            UpstreamError::CodeMismatch(message) => write!(f, "{}", message),
+            UpstreamError::DomSelectorsFailed(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for UpstreamError {}
+
+impl From<StateError> for UpstreamError {
+    fn from(error: StateError) -> Self {
+        match error {
+            StateError::NotFound => UpstreamError::StateMissing,
+            other => UpstreamError::StateMalformed(other.to_string()),
+        }
+    }
+}
@@ -0,0 +1,235 @@
+//! News, disclosures and earnings dates of a stock merged into one timeline,
+//! with Atom and RSS renderings.
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime, SecondsFormat, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::board::lenient_records;
+use crate::symbol::{code_calendar, code_timezone};
+use crate::{parse_market_time, parse_record_date};
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct MainStocksNews {
+    #[serde(deserialize_with = "lenient_records")]
+    articles: Vec<EventRecord>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct MainStocksDisclosure {
+    #[serde(deserialize_with = "lenient_records")]
+    items: Vec<EventRecord>,
+}
+
+/// A news article or disclosure item. The captured page's lists are empty, so
+/// items are read with the keys its other sections use: `title`, `link` and
+/// the `disclosedTime` of the earnings summary.
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct EventRecord {
+    title: Option<String>,
+    link: Option<String>,
+    disclosed_time: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct PressReleaseSchedule {
+    press_release_schedule_message: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct PressReleaseSummary {
+    disclosed_time: Option<String>,
+    summary: Option<String>,
+}
+
+/// What an [`Event`] is.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    News,
+    Disclosure,
+    /// The next scheduled earnings release.
+    EarningsSchedule,
+    /// The summary of the latest earnings release.
+    EarningsSummary,
+}
+
+impl EventKind {
+    fn label(self) -> &'static str {
+        match self {
+            EventKind::News => "news",
+            EventKind::Disclosure => "disclosure",
+            EventKind::EarningsSchedule => "earnings_schedule",
+            EventKind::EarningsSummary => "earnings_summary",
+        }
+    }
+}
+
+/// One entry of a stock's event timeline.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Event {
+    #[serde(rename = "type")]
+    pub kind: EventKind,
+    pub title: String,
+    pub link: Option<String>,
+    /// `None` when the page gives no usable time; such events sort last.
+    pub timestamp: Option<DateTime<FixedOffset>>,
+    /// The full text of an earnings summary.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// Collects the events on a stock page, oldest first. Partial times are read
+/// in the exchange's timezone and completed from `fetched_at`.
+pub fn extract_events(code: &str, state: &Value, fetched_at: DateTime<Utc>) -> Vec<Event> {
+    let tz = code_timezone(code);
+    let calendar = code_calendar(code);
+    let time = |raw: Option<&str>| raw.and_then(|r| parse_market_time(r, tz, calendar, fetched_at));
+    let section = |key: &str| state.get(key).unwrap_or(&Value::Null);
+    let mut events = Vec::new();
+
+    let news = MainStocksNews::deserialize(section("mainStocksNews")).unwrap_or_default();
+    let disclosures = MainStocksDisclosure::deserialize(section("mainStocksDisclosure")).unwrap_or_default();
+    let records = news
+        .articles
+        .into_iter()
+        .map(|r| (EventKind::News, r))
+        .chain(disclosures.items.into_iter().map(|r| (EventKind::Disclosure, r)));
+    for (kind, record) in records {
+        let Some(title) = record.title.filter(|t| !t.is_empty()) else {
+            continue;
+        };
+        events.push(Event {
+            kind,
+            title,
+            link: record.link.filter(|l| !l.is_empty()),
+            timestamp: time(record.disclosed_time.as_deref()),
+            detail: None,
+        });
+    }
+
+    let schedule = PressReleaseSchedule::deserialize(section("mainStocksPressReleaseSchedule")).unwrap_or_default();
+    if let Some(message) = schedule.press_release_schedule_message.filter(|m| !m.is_empty()) {
+        events.push(Event {
+            kind: EventKind::EarningsSchedule,
+            timestamp: find_date(&message)
+                .and_then(|date| tz.from_local_datetime(&date.and_time(NaiveTime::MIN)).earliest())
+                .map(|dt| dt.fixed_offset()),
+            title: message,
+            link: None,
+            detail: None,
+        });
+    }
+
+    let summary = PressReleaseSummary::deserialize(section("mainStocksPressReleaseSummary")).unwrap_or_default();
+    if let Some(text) = summary.summary.filter(|s| !s.is_empty()) {
+        // The first sentence names the period and headline figures.
+        let title = match text.find('。') {
+            Some(end) => text[..end + '。'.len_utf8()].to_string(),
+            None => text.clone(),
+        };
+        events.push(Event {
+            kind: EventKind::EarningsSummary,
+            title,
+            link: None,
+            timestamp: time(summary.disclosed_time.as_deref()),
+            detail: Some(text),
+        });
+    }
+
+    events.sort_by_key(|event| (event.timestamp.is_none(), event.timestamp));
+    events
+}
+
+/// Finds the first `2025年11月11日`-style date in a message.
+fn find_date(text: &str) -> Option<NaiveDate> {
+    text.char_indices()
+        .filter(|&(i, c)| c.is_ascii_digit() && !text[..i].ends_with(|p: char| p.is_ascii_digit()))
+        .find_map(|(start, _)| {
+            let end = start + text[start..].find('日')? + '日'.len_utf8();
+            parse_record_date(&text[start..end])
+        })
+}
+
+/// Renders the events as an Atom feed, newest first.
+pub fn events_to_atom(code: &str, events: &[Event], updated: DateTime<Utc>) -> String {
+    let updated = updated.to_rfc3339_opts(SecondsFormat::Secs, true);
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    xml.push_str(&format!("  <title>{} events</title>\n", escape(code)));
+    xml.push_str(&format!("  <id>urn:win-state:events:{}</id>\n", escape(code)));
+    xml.push_str(&format!("  <updated>{}</updated>\n", updated));
+    xml.push_str("  <author><name>Yahoo!ファイナンス</name></author>\n");
+    for event in events.iter().rev() {
+        let entry_updated = event
+            .timestamp
+            .map(|t| t.to_rfc3339_opts(SecondsFormat::Secs, true))
+            .unwrap_or_else(|| updated.clone());
+        xml.push_str("  <entry>\n");
+        xml.push_str(&format!("    <title>{}</title>\n", escape(&event.title)));
+        xml.push_str(&format!("    <id>{}</id>\n", escape(&entry_id(code, event))));
+        xml.push_str(&format!("    <updated>{}</updated>\n", entry_updated));
+        xml.push_str(&format!("    <category term=\"{}\"/>\n", event.kind.label()));
+        if let Some(link) = &event.link {
+            xml.push_str(&format!("    <link href=\"{}\"/>\n", escape(link)));
+        }
+        if let Some(detail) = &event.detail {
+            xml.push_str(&format!("    <summary>{}</summary>\n", escape(detail)));
+        }
+        xml.push_str("  </entry>\n");
+    }
+    xml.push_str("</feed>\n");
+    xml
+}
+
+/// Renders the events as an RSS 2.0 channel, newest first.
+pub fn events_to_rss(code: &str, events: &[Event], link: &str) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str("<rss version=\"2.0\">\n<channel>\n");
+    xml.push_str(&format!("  <title>{} events</title>\n", escape(code)));
+    xml.push_str(&format!("  <link>{}</link>\n", escape(link)));
+    xml.push_str(&format!("  <description>News, disclosures and earnings dates for {}</description>\n", escape(code)));
+    for event in events.iter().rev() {
+        xml.push_str("  <item>\n");
+        xml.push_str(&format!("    <title>{}</title>\n", escape(&event.title)));
+        if let Some(link) = &event.link {
+            xml.push_str(&format!("    <link>{}</link>\n", escape(link)));
+        }
+        xml.push_str(&format!(
+            "    <guid isPermaLink=\"false\">{}</guid>\n",
+            escape(&entry_id(code, event))
+        ));
+        if let Some(timestamp) = event.timestamp {
+            xml.push_str(&format!("    <pubDate>{}</pubDate>\n", timestamp.to_rfc2822()));
+        }
+        xml.push_str(&format!("    <category>{}</category>\n", event.kind.label()));
+        if let Some(detail) = &event.detail {
+            xml.push_str(&format!("    <description>{}</description>\n", escape(detail)));
+        }
+        xml.push_str("  </item>\n");
+    }
+    xml.push_str("</channel>\n</rss>\n");
+    xml
+}
+
+/// A stable entry id: the link when there is one, otherwise built from the
+/// event's type and time.
+fn entry_id(code: &str, event: &Event) -> String {
+    match (&event.link, event.timestamp) {
+        (Some(link), _) => link.clone(),
+        (None, Some(timestamp)) => format!("urn:win-state:{}:{}:{}", code, event.kind.label(), timestamp.timestamp()),
+        (None, None) => format!("urn:win-state:{}:{}", code, event.kind.label()),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,96 @@
+use serde_json::Value;
+
+use crate::StateError;
+
+const ASSIGNMENT_TARGET: &str = "window.__PRELOADED_STATE__";
+
+/// Byte range of the `__PRELOADED_STATE__` object literal within a page.
+///
+/// `start` points at the opening `{` and `end` is one past the matching `}`,
+/// so `&html[span.start..span.end]` is exactly the JSON text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StateSpan {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Extracts and parses the `window.__PRELOADED_STATE__` object from an HTML page.
+pub fn extract_preloaded_state(html: &str) -> Result<Value, StateError> {
+    let span = locate_preloaded_state(html)?;
+    serde_json::from_str(&html[span.start..span.end])
+        .map_err(|source| StateError::Malformed { span, source })
+}
+
+/// Finds the object literal assigned to `window.__PRELOADED_STATE__`.
+///
+/// The object is delimited by balanced braces rather than by `</script>`, so
+/// strings containing `</script>` or braces and statements following the
+/// assignment in the same script tag do not affect the result.
+pub fn locate_preloaded_state(html: &str) -> Result<StateSpan, StateError> {
+    let mut search_from = 0;
+    while let Some(found) = html[search_from..].find(ASSIGNMENT_TARGET) {
+        let after_target = search_from + found + ASSIGNMENT_TARGET.len();
+        search_from = after_target;
+
+        let Some(start) = assignment_value_start(html, after_target) else {
+            continue;
+        };
+        return match find_object_end(html.as_bytes(), start) {
+            Some(end) => Ok(StateSpan { start, end }),
+            None => Err(StateError::Unterminated { start }),
+        };
+    }
+    Err(StateError::NotFound)
+}
+
+/// Returns the offset of the `{` in `= {`, or `None` if `pos` is not followed by
+/// an assignment of an object literal (e.g. a comparison or a property read).
+fn assignment_value_start(html: &str, pos: usize) -> Option<usize> {
+    let rest = &html[pos..];
+    let after_ws = rest.trim_start();
+    let after_eq = after_ws.strip_prefix('=')?;
+    if after_eq.starts_with('=') {
+        return None;
+    }
+    let value = after_eq.trim_start();
+    if !value.starts_with('{') {
+        return None;
+    }
+    Some(html.len() - value.len())
+}
+
+/// Scans from the `{` at `start` and returns one past its matching `}`.
+///
+/// Tracks JavaScript string literals (`"`, `'` and template literals) and
+/// backslash escapes so that braces inside strings are ignored.
+fn find_object_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut quote: Option<u8> = None;
+    let mut escaped = false;
+
+    for (i, &b) in bytes.iter().enumerate().skip(start) {
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if b == b'\\' {
+                escaped = true;
+            } else if b == q {
+                quote = None;
+            }
+            continue;
+        }
+
+        match b {
+            b'"' | b'\'' | b'`' => quote = Some(b),
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
@@ -0,0 +1,141 @@
+//! Where pages come from. Parsing never fetches by itself: each binary passes
+//! a [`PageFetcher`] for the network it runs on, and tests pass one that reads
+//! saved pages.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use futures::future::{self, LocalBoxFuture};
+use serde::{Deserialize, Serialize};
+
+use crate::{url_host, UpstreamError};
+
+/// Fetches the body of a page by URL.
+pub trait PageFetcher {
+    fn fetch<'a>(&'a self, url: &'a str) -> LocalBoxFuture<'a, Result<String, UpstreamError>>;
+}
+
+impl<F: PageFetcher + ?Sized> PageFetcher for &F {
+    fn fetch<'a>(&'a self, url: &'a str) -> LocalBoxFuture<'a, Result<String, UpstreamError>> {
+        (**self).fetch(url)
+    }
+}
+
+impl<F: PageFetcher + ?Sized> PageFetcher for Box<F> {
+    fn fetch<'a>(&'a self, url: &'a str) -> LocalBoxFuture<'a, Result<String, UpstreamError>> {
+        (**self).fetch(url)
+    }
+}
+
+/// The fixture file name of a page URL: the path under `/quote/` (or the whole
+/// path for other pages) with `/` and `?` replaced by `_`, plus `.html`.
+/// `https://finance.yahoo.co.jp/quote/5016.T/` is `5016.T.html`,
+/// `.../quote/7203.T/history?page=2` is `7203.T_history_page=2.html`.
+pub fn fixture_name(url: &str) -> String {
+    let host = url_host(url);
+    let path = url.split_once(host).map_or(url, |(_, path)| path);
+    let path = path.split('#').next().unwrap_or(path);
+    let path = path.strip_prefix("/quote/").unwrap_or(path);
+    let path = path.trim_matches('/').replace("/?", "?");
+    format!("{}.html", path.replace(['/', '?', '&'], "_"))
+}
+
+/// Serves pages from a directory of saved pages named by [`fixture_name`].
+/// A page without a file is answered like a missing page upstream (HTTP 404).
+#[derive(Debug, Clone)]
+pub struct FixtureFetcher {
+    dir: PathBuf,
+}
+
+impl FixtureFetcher {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        FixtureFetcher { dir: dir.into() }
+    }
+
+    /// The file a URL is served from.
+    pub fn path(&self, url: &str) -> PathBuf {
+        self.dir.join(fixture_name(url))
+    }
+}
+
+impl PageFetcher for FixtureFetcher {
+    fn fetch<'a>(&'a self, url: &'a str) -> LocalBoxFuture<'a, Result<String, UpstreamError>> {
+        let page = std::fs::read_to_string(self.path(url)).map_err(|_| UpstreamError::HttpStatus(404));
+        Box::pin(future::ready(page))
+    }
+}
+
+/// Page bodies by URL, as captured by a [`RecordingFetcher`] and served by a
+/// [`ReplayFetcher`]. Saved as JSON.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Recording {
+    pub pages: BTreeMap<String, String>,
+}
+
+impl Recording {
+    pub fn insert(&mut self, url: impl Into<String>, body: impl Into<String>) {
+        self.pages.insert(url.into(), body.into());
+    }
+
+    pub fn get(&self, url: &str) -> Option<&str> {
+        self.pages.get(url).map(String::as_str)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        serde_json::from_str(&json).map_err(std::io::Error::other)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+}
+
+/// Passes requests to another fetcher and records the pages it returns.
+pub struct RecordingFetcher<F> {
+    inner: F,
+    recording: RefCell<Recording>,
+}
+
+impl<F: PageFetcher> RecordingFetcher<F> {
+    pub fn new(inner: F) -> Self {
+        RecordingFetcher { inner, recording: RefCell::new(Recording::default()) }
+    }
+
+    /// The pages recorded so far.
+    pub fn recording(&self) -> Recording {
+        self.recording.borrow().clone()
+    }
+}
+
+impl<F: PageFetcher> PageFetcher for RecordingFetcher<F> {
+    fn fetch<'a>(&'a self, url: &'a str) -> LocalBoxFuture<'a, Result<String, UpstreamError>> {
+        Box::pin(async move {
+            let body = self.inner.fetch(url).await?;
+            self.recording.borrow_mut().insert(url, body.clone());
+            Ok(body)
+        })
+    }
+}
+
+/// Serves the pages of a [`Recording`]. An unrecorded URL is answered like a
+/// missing page upstream (HTTP 404).
+#[derive(Debug, Clone, Default)]
+pub struct ReplayFetcher {
+    recording: Recording,
+}
+
+impl ReplayFetcher {
+    pub fn new(recording: Recording) -> Self {
+        ReplayFetcher { recording }
+    }
+}
+
+impl PageFetcher for ReplayFetcher {
+    fn fetch<'a>(&'a self, url: &'a str) -> LocalBoxFuture<'a, Result<String, UpstreamError>> {
+        let page = self.recording.get(url).map(str::to_string).ok_or(UpstreamError::HttpStatus(404));
+        Box::pin(future::ready(page))
+    }
+}
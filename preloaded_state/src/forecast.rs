@@ -0,0 +1,97 @@
+//! Median target price and rating breakdown from the forecast sections.
+//!
+//! Analyst consensus lives in `mainStocksForecast` (or in the `stockForecast`
+//! block of `mainStocksNews`); when neither carries figures the community
+//! forecast in `stockPredictions.thisWeekUserPredictionData` is used instead.
+//! All three are read with the community forecast's keys, the only ones a
+//! captured page has filled in.
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::board::{lenient_records, lenient_string};
+use crate::{parse_decimal, parse_rate, Rate};
+
+/// Forecast sections in order of preference, as JSON pointers.
+const FORECAST_SECTIONS: &[&str] = &[
+    "/mainStocksForecast",
+    "/mainStocksNews/stockForecast",
+    "/stockPredictions/thisWeekUserPredictionData",
+];
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct ForecastSection {
+    #[serde(deserialize_with = "lenient_string")]
+    median_forecast_price: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    median_forecast_increase_rate: Option<String>,
+    total_votes: Option<u64>,
+    #[serde(deserialize_with = "lenient_records")]
+    breakdown: Vec<RatingRecord>,
+}
+
+/// A bucket of the breakdown.
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct RatingRecord {
+    #[serde(deserialize_with = "lenient_string")]
+    rate: Option<String>,
+    votes: Option<u64>,
+    #[serde(deserialize_with = "lenient_string")]
+    closing_price: Option<String>,
+}
+
+/// One bucket of the rating breakdown: a price band (`"+5%"`) with the votes
+/// it received.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ForecastRating {
+    pub label: String,
+    pub votes: Option<u64>,
+    /// Target price of the band.
+    pub price: Option<Decimal>,
+}
+
+/// Median target price and rating breakdown. Each `*_raw` field keeps the
+/// string the value was parsed from.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Forecast {
+    pub median_target: Option<Decimal>,
+    pub median_target_raw: Option<String>,
+    /// Expected change from the current price to `median_target`.
+    pub median_target_rate: Option<Rate>,
+    pub median_target_rate_raw: Option<String>,
+    pub total_votes: Option<u64>,
+    pub ratings: Vec<ForecastRating>,
+    /// JSON pointer of the section the figures were read from.
+    pub source: &'static str,
+}
+
+/// Extracts the first forecast section with a median target, or `None` when
+/// the page has none.
+pub fn extract_forecast(state: &Value) -> Option<Forecast> {
+    FORECAST_SECTIONS.iter().find_map(|&pointer| {
+        let section = ForecastSection::deserialize(state.pointer(pointer)?).ok()?;
+        let median_target = section.median_forecast_price.as_deref().and_then(parse_decimal)?;
+        Some(Forecast {
+            median_target: Some(median_target),
+            median_target_raw: section.median_forecast_price,
+            median_target_rate: section.median_forecast_increase_rate.as_deref().and_then(parse_rate),
+            median_target_rate_raw: section.median_forecast_increase_rate,
+            total_votes: section.total_votes,
+            ratings: section
+                .breakdown
+                .into_iter()
+                .filter_map(|record| {
+                    Some(ForecastRating {
+                        label: record.rate?,
+                        votes: record.votes,
+                        price: record.closing_price.as_deref().and_then(parse_decimal),
+                    })
+                })
+                .collect(),
+            source: pointer,
+        })
+    })
+}
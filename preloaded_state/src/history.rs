@@ -0,0 +1,142 @@
+//! Daily price history from `mainStocksHistory`, populated on a stock's
+//! `/history` page. Paging comes from its own `paging` object; the sibling
+//! `mainStocksHistoryPage` only carries the page's `tabCategory` and is not read.
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::board::{lenient_records, lenient_string};
+use crate::{parse_decimal, parse_record_date, Paged, Paging};
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct MainStocksHistory {
+    history: HistorySection,
+    paging: Paging,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct HistorySection {
+    #[serde(deserialize_with = "lenient_string")]
+    year_high_price: Option<String>,
+    year_high_price_date: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    year_low_price: Option<String>,
+    year_low_price_date: Option<String>,
+    #[serde(deserialize_with = "lenient_records")]
+    histories: Vec<HistoryRecord>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct HistoryRecord {
+    base_datetime: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    open_price: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    high_price: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    low_price: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    close_price: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    volume: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    adjusted_close_price: Option<String>,
+}
+
+/// One day of OHLCV data.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DailyBar {
+    pub date: NaiveDate,
+    pub open: Option<Decimal>,
+    pub high: Option<Decimal>,
+    pub low: Option<Decimal>,
+    pub close: Option<Decimal>,
+    pub volume: Option<Decimal>,
+    pub adjusted_close: Option<Decimal>,
+}
+
+/// The 52-week high or low shown above the history table.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct YearExtreme {
+    pub price: Option<Decimal>,
+    pub date: Option<NaiveDate>,
+}
+
+/// A page (or several merged pages) of price history.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct PriceHistory {
+    pub year_high: YearExtreme,
+    pub year_low: YearExtreme,
+    /// Oldest first.
+    pub bars: Vec<DailyBar>,
+    pub paging: Paging,
+}
+
+impl Paged for PriceHistory {
+    fn paging(&self) -> Paging {
+        self.paging
+    }
+
+    fn merge(&mut self, next: PriceHistory) {
+        self.bars.extend(next.bars);
+        self.bars.sort_by_key(|bar| bar.date);
+        self.bars.dedup_by_key(|bar| bar.date);
+        self.paging = next.paging;
+    }
+}
+
+impl PriceHistory {
+    /// Renders the bars as CSV with a header row; missing values are left empty.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("date,open,high,low,close,volume,adjusted_close\n");
+        for bar in &self.bars {
+            let fields = [bar.open, bar.high, bar.low, bar.close, bar.volume, bar.adjusted_close]
+                .map(|value| value.map(|v| v.to_string()).unwrap_or_default());
+            csv.push_str(&format!("{},{}\n", bar.date, fields.join(",")));
+        }
+        csv
+    }
+}
+
+/// Extracts the price history page, or `None` when the state has no
+/// `mainStocksHistory` section. Records without a parseable date are skipped.
+pub fn extract_price_history(state: &Value) -> Option<PriceHistory> {
+    let section = state.get("mainStocksHistory")?;
+    let MainStocksHistory { history, paging } = MainStocksHistory::deserialize(section).ok()?;
+
+    let number = |raw: Option<String>| raw.as_deref().and_then(parse_decimal);
+    let mut bars: Vec<DailyBar> = history
+        .histories
+        .into_iter()
+        .filter_map(|record| {
+            Some(DailyBar {
+                date: record.base_datetime.as_deref().and_then(parse_record_date)?,
+                open: number(record.open_price),
+                high: number(record.high_price),
+                low: number(record.low_price),
+                close: number(record.close_price),
+                volume: number(record.volume),
+                adjusted_close: number(record.adjusted_close_price),
+            })
+        })
+        .collect();
+    bars.sort_by_key(|bar| bar.date);
+
+    Some(PriceHistory {
+        year_high: YearExtreme {
+            price: number(history.year_high_price),
+            date: history.year_high_price_date.as_deref().and_then(parse_record_date),
+        },
+        year_low: YearExtreme {
+            price: number(history.year_low_price),
+            date: history.year_low_price_date.as_deref().and_then(parse_record_date),
+        },
+        bars,
+        paging,
+    })
+}
@@ -0,0 +1,63 @@
+//! Shared parsing of the `window.__PRELOADED_STATE__` JSON embedded in
+//! Yahoo! Finance Japan quote pages.
+//!
+//! Used by the Cloudflare worker (wasm32), `selector.rs` and the
+//! `rust_extractor` CLI so that every binary parses pages the same way.
+
+mod board;
+mod cache;
+mod calendar;
+mod chart;
+mod consistency;
+mod currency;
+mod data_source;
+mod detail;
+mod discover;
+mod dividend;
+mod error;
+mod events;
+mod extract;
+mod fetcher;
+mod forecast;
+mod history;
+mod margin;
+mod normalize;
+mod output;
+mod paging;
+mod paths;
+mod profile;
+mod related;
+mod schedule;
+mod sentiment;
+mod symbol;
+mod time;
+
+pub use board::{CurrencyPrice, FundPrice, IndexPrice, Industry, PriceBoard, Quote, UsStock};
+pub use cache::{
+    cache_key, cache_ttl, CachedPage, MemoryCache, PageStore, CLOSED_TTL_SECONDS, HOLIDAY_TTL_SECONDS, TRADING_TTL_SECONDS,
+};
+pub use calendar::{is_tse_holiday, tse_holiday, tse_session, TseSession, TSE_CALENDAR_YEARS};
+pub use chart::{extract_chart, Chart, ChartPoint};
+pub use consistency::{verify_sources, Consistency, Mismatch};
+pub use currency::{extract_us_quote, extract_usd_jpy, UsQuote};
+pub use data_source::{parse_data_sources, validate_data_sources, Board, DataSource};
+pub use detail::{extract_stock_detail, DetailValue, PriceLimit, StockDetail};
+pub use discover::{discover_symbol, DiscoveredData, RankedCandidate};
+pub use dividend::{extract_dividend, Dividend};
+pub use error::{RegistryError, StateError, SymbolError, UpstreamError};
+pub use events::{events_to_atom, events_to_rss, extract_events, Event, EventKind};
+pub use extract::{extract_preloaded_state, locate_preloaded_state, StateSpan};
+pub use fetcher::{fixture_name, FixtureFetcher, PageFetcher, Recording, RecordingFetcher, ReplayFetcher};
+pub use forecast::{extract_forecast, Forecast, ForecastRating};
+pub use history::{extract_price_history, DailyBar, PriceHistory, YearExtreme};
+pub use margin::{extract_margin_history, MarginEntry, MarginHistory};
+pub use normalize::{parse_decimal, parse_rate, Rate, TypedQuote};
+pub use output::{process_dom_data, process_json_data, Format, OutputOptions};
+pub use paging::{Paged, Paging};
+pub use paths::{find_object, find_object_paths, list_keys};
+pub use profile::{extract_company_profile, CompanyProfile, IndustryInfo, ProfileItem};
+pub use related::{extract_related, AttentionRanking, RankedStock, Related, RelatedItem};
+pub use schedule::{url_host, Clock, FetchScheduler, SchedulerConfig, MAX_RETRY_DELAY_MILLIS};
+pub use sentiment::{extract_sentiment, SentimentSnapshot, VotingWindow};
+pub use symbol::{distinct_symbols, Market, Symbol};
+pub use time::{exchange_timezone, parse_market_time, parse_record_date, TradingCalendar};
@@ -0,0 +1,150 @@
+//! Margin trading balances from `mainStocksMarginHistory`, populated on a
+//! stock's `/margin` page.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use chrono_tz::Asia::Tokyo;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::board::{lenient_records, lenient_string};
+use crate::{parse_decimal, parse_record_date, Paged, Paging};
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct MainStocksMarginHistory {
+    #[serde(deserialize_with = "lenient_records")]
+    histories: Vec<MarginRecord>,
+    paging: Paging,
+}
+
+/// One row of the margin table, spelled like the
+/// `mainStocksDetail.marginTransactionInfo` summary (`"10/24"`, `"18,288,400"`).
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct MarginRecord {
+    margin_transaction_info_date: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    margin_transaction_buy: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    margin_transaction_sell: Option<String>,
+}
+
+/// Credit balances on one date, with the derived ratio and weekly changes.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct MarginEntry {
+    pub date: NaiveDate,
+    /// Outstanding credit-buy balance (shares).
+    pub buy_balance: Option<Decimal>,
+    /// Outstanding credit-sell balance (shares).
+    pub sell_balance: Option<Decimal>,
+    /// `buy_balance / sell_balance` to two places (信用倍率); None when
+    /// nothing is sold short.
+    pub margin_ratio: Option<Decimal>,
+    /// Change against the latest entry at least a week older.
+    pub buy_change_week: Option<Decimal>,
+    pub sell_change_week: Option<Decimal>,
+}
+
+/// A page (or several merged pages) of margin balances.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct MarginHistory {
+    /// Oldest first.
+    pub entries: Vec<MarginEntry>,
+    pub paging: Paging,
+}
+
+impl Paged for MarginHistory {
+    fn paging(&self) -> Paging {
+        self.paging
+    }
+
+    fn merge(&mut self, mut next: MarginHistory) {
+        // A later page is older than every entry so far; a year-less date
+        // that was placed after them belongs to an earlier year.
+        if let Some(oldest) = self.entries.first().map(|entry| entry.date) {
+            next.entries.retain_mut(|entry| {
+                while entry.date > oldest {
+                    match entry.date.with_year(entry.date.year() - 1) {
+                        Some(date) => entry.date = date,
+                        None => return false,
+                    }
+                }
+                true
+            });
+        }
+        self.entries.extend(next.entries);
+        self.paging = next.paging;
+        self.recompute();
+    }
+}
+
+impl MarginHistory {
+    /// Sorts the entries and fills the ratio and week-over-week changes.
+    fn recompute(&mut self) {
+        self.entries.sort_by_key(|entry| entry.date);
+        self.entries.dedup_by_key(|entry| entry.date);
+
+        for i in 0..self.entries.len() {
+            let entry = &self.entries[i];
+            let week_ago = entry.date - Duration::days(7);
+            let previous = self.entries[..i].iter().rev().find(|e| e.date <= week_ago);
+            let delta = |now: Option<Decimal>, then: Option<Decimal>| Some(now? - then?);
+            let buy_change_week = previous.and_then(|p| delta(entry.buy_balance, p.buy_balance));
+            let sell_change_week = previous.and_then(|p| delta(entry.sell_balance, p.sell_balance));
+            let margin_ratio = match (entry.buy_balance, entry.sell_balance) {
+                (Some(buy), Some(sell)) if !sell.is_zero() => Some((buy / sell).round_dp(2)),
+                _ => None,
+            };
+
+            let entry = &mut self.entries[i];
+            entry.margin_ratio = margin_ratio;
+            entry.buy_change_week = buy_change_week;
+            entry.sell_change_week = sell_change_week;
+        }
+    }
+}
+
+/// Extracts the margin history page, or `None` when the state has no
+/// `mainStocksMarginHistory` section. Rows without a parseable date are skipped.
+///
+/// Rows are listed newest first. A year-less date (`"10/24"`) is the latest
+/// such date not after the row above it, starting from `fetched_at`.
+pub fn extract_margin_history(state: &Value, fetched_at: DateTime<Utc>) -> Option<MarginHistory> {
+    let section = state.get("mainStocksMarginHistory")?;
+    let MainStocksMarginHistory { histories, paging } = MainStocksMarginHistory::deserialize(section).ok()?;
+
+    let number = |raw: Option<String>| raw.as_deref().and_then(parse_decimal);
+    let mut newer = fetched_at.with_timezone(&Tokyo).date_naive();
+    let entries = histories
+        .into_iter()
+        .filter_map(|record| {
+            let raw = record.margin_transaction_info_date?;
+            let date = parse_record_date(&raw).or_else(|| month_day_not_after(&raw, newer))?;
+            newer = date;
+            Some(MarginEntry {
+                date,
+                buy_balance: number(record.margin_transaction_buy),
+                sell_balance: number(record.margin_transaction_sell),
+                margin_ratio: None,
+                buy_change_week: None,
+                sell_change_week: None,
+            })
+        })
+        .collect();
+
+    let mut history = MarginHistory { entries, paging };
+    history.recompute();
+    Some(history)
+}
+
+/// Reads `"10/24"` as the latest 24 October not after `limit`.
+fn month_day_not_after(raw: &str, limit: NaiveDate) -> Option<NaiveDate> {
+    let (month, day) = raw.trim().split_once('/')?;
+    let (month, day) = (month.parse().ok()?, day.parse().ok()?);
+    let this_year = NaiveDate::from_ymd_opt(limit.year(), month, day);
+    match this_year {
+        Some(date) if date <= limit => Some(date),
+        _ => NaiveDate::from_ymd_opt(limit.year() - 1, month, day),
+    }
+}
@@ -0,0 +1,106 @@
+//! Conversion of Yahoo's display strings (`"3,456.5"`, `"+12.5"`, `"(-3.68%)"`)
+//! into exact decimals.
+//!
+//! Decimals serialize as JSON strings so no digits are lost to `f64`.
+
+use std::str::FromStr;
+
+use chrono::{DateTime, FixedOffset, Utc};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::{parse_market_time, Quote, TradingCalendar};
+
+/// A percentage rate, reported both as shown on the page and as a fraction.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rate {
+    /// `-3.68` for `"-3.68%"`.
+    pub percent: Decimal,
+    /// `-0.0368` for `"-3.68%"`.
+    pub fraction: Decimal,
+}
+
+impl Rate {
+    pub fn from_percent(percent: Decimal) -> Self {
+        Rate {
+            percent,
+            fraction: percent / Decimal::ONE_HUNDRED,
+        }
+    }
+}
+
+/// A `Quote` with numeric values and the update time parsed. Each `*_raw`
+/// field keeps the string the value was parsed from.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TypedQuote {
+    pub code: String,
+    pub name: Option<String>,
+    pub price: Option<Decimal>,
+    pub price_raw: Option<String>,
+    pub price_change: Option<Decimal>,
+    pub price_change_raw: Option<String>,
+    pub price_change_rate: Option<Rate>,
+    pub price_change_rate_raw: Option<String>,
+    pub dividend_yield: Option<Rate>,
+    pub dividend_yield_raw: Option<String>,
+    pub update_time: Option<DateTime<FixedOffset>>,
+    pub update_time_raw: Option<String>,
+}
+
+impl TypedQuote {
+    /// Parses `quote`, reading its update time in `tz` and completing it from
+    /// `fetched_at` by `calendar` (see [`parse_market_time`]).
+    pub fn new(quote: &Quote, tz: Tz, calendar: TradingCalendar, fetched_at: DateTime<Utc>) -> Self {
+        TypedQuote {
+            code: quote.code.clone(),
+            name: quote.name.clone(),
+            price: quote.price.as_deref().and_then(parse_decimal),
+            price_raw: quote.price.clone(),
+            price_change: quote.price_change.as_deref().and_then(parse_decimal),
+            price_change_raw: quote.price_change.clone(),
+            price_change_rate: quote.price_change_rate.as_deref().and_then(parse_rate),
+            price_change_rate_raw: quote.price_change_rate.clone(),
+            dividend_yield: quote.dividend_yield.as_deref().and_then(parse_rate),
+            dividend_yield_raw: quote.dividend_yield.clone(),
+            update_time: quote
+                .update_time
+                .as_deref()
+                .and_then(|raw| parse_market_time(raw, tz, calendar, fetched_at)),
+            update_time_raw: quote.update_time.clone(),
+        }
+    }
+}
+
+/// Parses a displayed number such as `"3,456.5"`, `"+77"`, `"－１２．５"` or
+/// `"(+0.52%)"`. Returns `None` for placeholders like `"---"` or `""`.
+pub fn parse_decimal(raw: &str) -> Option<Decimal> {
+    let ascii: String = raw
+        .chars()
+        .map(to_ascii)
+        .filter(|c| !matches!(c, ',' | ' '))
+        .collect();
+    let trimmed = ascii
+        .trim()
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .trim_end_matches('%');
+    let unsigned = trimmed.strip_prefix('+').unwrap_or(trimmed);
+    Decimal::from_str(unsigned).ok()
+}
+
+/// Parses a percentage such as `"-3.68"`, `"+0.52%"` or `"0.89"`.
+pub fn parse_rate(raw: &str) -> Option<Rate> {
+    parse_decimal(raw).map(Rate::from_percent)
+}
+
+/// Maps full-width ASCII variants (`０`-`９`, `＋`, `－`, `．`, `，`, `％`, ...)
+/// and the Unicode minus sign to their ASCII forms.
+pub(crate) fn to_ascii(c: char) -> char {
+    match c {
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        '\u{2212}' => '-',
+        '\u{3000}' => ' ',
+        _ => c,
+    }
+}
@@ -0,0 +1,302 @@
+//! The per-code quote output: the price board matching a code, read from the
+//! embedded state or, for pages without one, from the DOM, and rendered with
+//! the requested keys and format.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use scraper::{Html, Selector};
+use serde_json::{Map, Value};
+
+use crate::symbol::{code_calendar, code_timezone};
+use crate::{
+    extract_dividend, extract_forecast, extract_us_quote, find_object, find_object_paths, Board,
+    DataSource, Quote, Symbol, TypedQuote, UpstreamError,
+};
+
+/// Keys served from `mainStocksDividend` (see `preloaded_state::Dividend`).
+const DIVIDEND_KEYS: &[&str] = &["dps", "dps_date", "dividend_yield", "dividend_yield_time", "payout_ratio"];
+/// Keys served from the forecast sections (see `preloaded_state::Forecast`).
+const FORECAST_KEYS: &[&str] = &["median_target", "median_target_rate", "total_votes", "ratings"];
+/// Keys served from the US listing (see `preloaded_state::UsQuote`).
+const US_KEYS: &[&str] = &["currency", "usd_price", "jpy_price", "usd_jpy", "us_market", "us_link"];
+
+/// Output format selected with the `format` query parameter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// Values exactly as displayed on the page (default).
+    Raw,
+    /// Numbers parsed into decimals, with the display string kept in `<key>_raw`.
+    Typed,
+}
+
+/// How matched data is rendered for a single code.
+#[derive(Debug, Clone)]
+pub struct OutputOptions {
+    /// Keys from the `keys` query parameter; None returns every field.
+    pub keys: Option<Vec<String>>,
+    pub format: Format,
+    /// When the page was fetched; completes partial update times in typed output.
+    pub fetched_at: DateTime<Utc>,
+    /// USD/JPY rate for `jpy_price`, fetched once per request when needed.
+    pub usd_jpy: Option<Decimal>,
+}
+
+/// Processes the __PRELOADED_STATE__ JSON data to find financial info.
+pub fn process_json_data(
+    code: &str,
+    symbol: &Symbol,
+    data: &Value,
+    data_sources: &[DataSource],
+    output: &OutputOptions,
+) -> Result<Map<String, Value>, UpstreamError> {
+    // 1. Try predefined paths
+    for source in data_sources {
+        if let Some(target_obj) = find_object(data, &source.path_segments()) {
+            if let Some(quote) = source.decode(target_obj) {
+                if symbol.matches_board_code(&quote.code) {
+                    let mut results = build_json_result(code, target_obj, &quote, output, "json_predefined");
+                    add_section_fields(data, output, &mut results);
+                    add_us_fields(data, symbol, &quote, output, &mut results);
+                    return Ok(results);
+                }
+            }
+        }
+    }
+
+    // 2. Fallback to generic key search, decoding candidates as a stock price board
+    let fallback_keys_to_find = vec!["code".to_string()];
+    let mut found_paths = Vec::new();
+    find_object_paths(data, &fallback_keys_to_find, &mut Vec::new(), &mut found_paths);
+
+    for path in found_paths {
+        let mut target_obj = data;
+        for &key in &path {
+            target_obj = &target_obj[key];
+        }
+        if let Some(obj_map) = target_obj.as_object() {
+            if let Some(quote) = Board::Stock.decode(obj_map) {
+                // Objects without a price, such as `pageInfo`, are not price boards
+                if symbol.matches_board_code(&quote.code) && quote.price.is_some() {
+                    let mut results = build_json_result(code, obj_map, &quote, output, "json_fallback");
+                    add_section_fields(data, output, &mut results);
+                    add_us_fields(data, symbol, &quote, output, &mut results);
+                    return Ok(results);
+                }
+            }
+        }
+    }
+
+    Err(UpstreamError::CodeMismatch("Could not find matching data in JSON.".to_string()))
+}
+
+/// Builds the response map for a matched board: the requested `keys` from the
+/// decoded quote, or the entire raw object when no keys were requested.
+fn build_json_result(
+    code: &str,
+    raw: &Map<String, Value>,
+    quote: &Quote,
+    output: &OutputOptions,
+    source: &str,
+) -> Map<String, Value> {
+    let mut results = if output.keys.is_none() && output.format == Format::Raw {
+        // If keys is None, return the entire target_obj
+        let mut results = raw.clone();
+        results.insert("code".to_string(), Value::String(code.to_string())); // Ensure code is present
+        results
+    } else {
+        select_quote_fields(code, quote, output.keys.as_ref(), output)
+    };
+    results.insert("status".to_string(), Value::String("OK".to_string()));
+    results.insert("source".to_string(), Value::String(source.to_string()));
+    results
+}
+
+/// Serializes `quote` in the requested format and keeps the requested `keys`
+/// (all fields when `keys` is None). In typed format each key also brings its
+/// `<key>_raw` sibling.
+fn select_quote_fields(
+    code: &str,
+    quote: &Quote,
+    keys: Option<&Vec<String>>,
+    output: &OutputOptions,
+) -> Map<String, Value> {
+    let serialized = match output.format {
+        Format::Raw => serde_json::to_value(quote),
+        Format::Typed => {
+            let typed = TypedQuote::new(quote, code_timezone(code), code_calendar(code), output.fetched_at);
+            serde_json::to_value(typed)
+        }
+    };
+    let mut fields = match serialized {
+        Ok(Value::Object(fields)) => fields,
+        _ => Map::new(),
+    };
+    fields.retain(|_, value| !value.is_null());
+    fields.insert("code".to_string(), Value::String(code.to_string()));
+
+    let Some(keys_vec) = keys else {
+        return fields;
+    };
+    let mut results = Map::new();
+    for key in keys_vec {
+        if let Some(value) = fields.get(key.as_str()) {
+            results.insert(key.clone(), value.clone());
+        }
+        let raw_key = format!("{}_raw", key);
+        if let Some(value) = fields.get(&raw_key) {
+            results.insert(raw_key, value.clone());
+        }
+    }
+    results
+}
+
+/// Adds requested keys that the price board did not provide from the dividend
+/// and forecast sections.
+fn add_section_fields(data: &Value, output: &OutputOptions, results: &mut Map<String, Value>) {
+    let Some(keys) = &output.keys else {
+        return;
+    };
+    let missing: Vec<&String> = keys.iter().filter(|key| !results.contains_key(key.as_str())).collect();
+    let wants = |section_keys: &[&str]| missing.iter().any(|key| section_keys.contains(&key.as_str()));
+
+    let mut fields = Map::new();
+    if wants(DIVIDEND_KEYS) {
+        if let Some(Ok(Value::Object(dividend))) = extract_dividend(data).map(serde_json::to_value) {
+            fields.extend(dividend);
+        }
+    }
+    if wants(FORECAST_KEYS) {
+        if let Some(Ok(Value::Object(forecast))) = extract_forecast(data).map(serde_json::to_value) {
+            fields.extend(forecast);
+        }
+    }
+    fields.retain(|_, value| !value.is_null());
+    copy_fields(&fields, missing.into_iter().map(String::as_str), output.format, results);
+}
+
+/// Adds the US listing fields: the requested `US_KEYS`, or all of them for a
+/// US ticker when no keys were requested.
+fn add_us_fields(data: &Value, symbol: &Symbol, quote: &Quote, output: &OutputOptions, results: &mut Map<String, Value>) {
+    let wanted: Vec<&str> = match &output.keys {
+        Some(keys) => keys.iter().map(String::as_str).filter(|key| US_KEYS.contains(key)).collect(),
+        None if matches!(symbol, Symbol::UsStock(_)) => US_KEYS.to_vec(),
+        None => return,
+    };
+    if wanted.is_empty() {
+        return;
+    }
+    let Some(mut us_quote) = extract_us_quote(data, symbol, quote) else {
+        return;
+    };
+    if let Some(rate) = output.usd_jpy {
+        us_quote.convert(rate);
+    }
+    let Ok(Value::Object(mut fields)) = serde_json::to_value(us_quote) else {
+        return;
+    };
+    fields.retain(|_, value| !value.is_null());
+    copy_fields(&fields, wanted.into_iter(), output.format, results);
+}
+
+/// Copies `keys` from `fields` into `results`. Raw format takes the display
+/// string (`<key>_raw`) where there is one; typed format takes the parsed
+/// value and its `_raw` sibling.
+fn copy_fields<'a>(
+    fields: &Map<String, Value>,
+    keys: impl Iterator<Item = &'a str>,
+    format: Format,
+    results: &mut Map<String, Value>,
+) {
+    for key in keys {
+        let raw_key = format!("{}_raw", key);
+        match format {
+            Format::Raw => {
+                if let Some(value) = fields.get(&raw_key).or_else(|| fields.get(key)) {
+                    results.insert(key.to_string(), value.clone());
+                }
+            }
+            Format::Typed => {
+                if let Some(value) = fields.get(key) {
+                    results.insert(key.to_string(), value.clone());
+                }
+                if let Some(value) = fields.get(&raw_key) {
+                    results.insert(raw_key, value.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Processes the HTML body using CSS selectors as a fallback.
+pub fn process_dom_data(code: &str, body: &str, output: &OutputOptions) -> Result<Map<String, Value>, UpstreamError> {
+    let document = Html::parse_document(body);
+    let mut results = Map::new();
+
+    // Create a map of known keys to their selectors
+    let mut selector_map = HashMap::new();
+    selector_map.insert("name", "h1");
+    selector_map.insert("price", "div[class*='_CommonPriceBoard__priceBlock'] span[class*='_StyledNumber__value']");
+    selector_map.insert("price_change", "span[class*='_PriceChangeLabel__primary'] span[class*='_StyledNumber__value']");
+    selector_map.insert("price_change_rate", "span[class*='_PriceChangeLabel__secondary'] span[class*='_StyledNumber__value']");
+    selector_map.insert("update_time", "li[class*='_CommonPriceBoard__time'] time, span[class*='_Time']");
+
+    let keys_to_process = if let Some(k) = &output.keys {
+        k.clone()
+    } else {
+        // Default keys if not provided (for DOM, this means all known fields)
+        vec![
+            "code".to_string(),
+            "name".to_string(),
+            "price".to_string(),
+            "price_change".to_string(),
+            "price_change_rate".to_string(),
+            "update_time".to_string(),
+        ]
+    };
+
+    for key in &keys_to_process {
+        let value = match key.as_str() {
+            "code" => Some(code.to_string()),
+            _ => {
+                if let Some(selector_str) = selector_map.get(key.as_str()) {
+                    let selector = Selector::parse(selector_str).unwrap();
+                    document.select(&selector).next().map(|el| el.text().collect::<String>().trim().to_string())
+                } else {
+                    None
+                }
+            }
+        };
+        if let Some(val) = value {
+            results.insert(key.clone(), Value::String(val));
+        }
+    }
+
+    // Ensure essential keys are present if requested, or if no keys were requested (defaults used)
+    if keys_to_process.contains(&"name".to_string()) && !results.contains_key("name") {
+         return Err(UpstreamError::DomSelectorsFailed("Failed to scrape essential data (name) from DOM.".to_string()));
+    }
+    if keys_to_process.contains(&"price".to_string()) && !results.contains_key("price") {
+         return Err(UpstreamError::DomSelectorsFailed("Failed to scrape essential data (price) from DOM.".to_string()));
+    }
+
+    if output.format == Format::Typed {
+        let text = |key: &str| results.get(key).and_then(Value::as_str).map(str::to_string);
+        let quote = Quote {
+            code: code.to_string(),
+            name: text("name"),
+            price: text("price"),
+            price_change: text("price_change"),
+            price_change_rate: text("price_change_rate"),
+            dividend_yield: None,
+            update_time: text("update_time"),
+        };
+        results = select_quote_fields(code, &quote, Some(&keys_to_process), output);
+    }
+
+    results.insert("status".to_string(), Value::String("OK".to_string()));
+    results.insert("source".to_string(), Value::String("dom_fallback".to_string()));
+
+    Ok(results)
+}
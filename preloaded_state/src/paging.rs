@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+/// The `paging` object of paged sections (`mainStocksHistory`,
+/// `mainStocksMarginHistory`, `mainStocksNews`, ...).
+///
+/// Yahoo serves one page per request; `page` is 1-based and `0` when the
+/// section is empty.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Paging {
+    pub page: u32,
+    pub total_page: u32,
+    pub total_size: u32,
+    pub has_next: bool,
+}
+
+impl Paging {
+    /// The page to request next, if any.
+    pub fn next_page(&self) -> Option<u32> {
+        (self.has_next && self.page < self.total_page).then_some(self.page + 1)
+    }
+}
+
+/// A section that is served one page at a time.
+pub trait Paged {
+    fn paging(&self) -> Paging;
+
+    /// Appends the entries of a following page and takes over its paging.
+    fn merge(&mut self, next: Self);
+}
@@ -0,0 +1,66 @@
+use serde_json::{Map, Value};
+
+/// Follows `path` from `value` and returns the object found there, if any.
+pub fn find_object<'a>(value: &'a Value, path: &[&str]) -> Option<&'a Map<String, Value>> {
+    let mut current = value;
+    for key in path {
+        current = current.get(key)?;
+    }
+    current.as_object()
+}
+
+/// Recursively finds paths to objects that contain all the specified keys.
+///
+/// Arrays are traversed transparently, so a path only lists object keys.
+pub fn find_object_paths<'a>(
+    value: &'a Value,
+    keys_to_find: &[String],
+    current_path: &mut Vec<&'a str>,
+    found_paths: &mut Vec<Vec<&'a str>>,
+) {
+    if let Value::Object(map) = value {
+        if keys_to_find.iter().all(|key| map.contains_key(key)) {
+            found_paths.push(current_path.clone());
+        }
+        for (key, nested_value) in map {
+            current_path.push(key);
+            find_object_paths(nested_value, keys_to_find, current_path, found_paths);
+            current_path.pop(); // Backtrack
+        }
+    } else if let Value::Array(arr) = value {
+        for nested_value in arr {
+            find_object_paths(nested_value, keys_to_find, current_path, found_paths);
+        }
+    }
+}
+
+/// Lists every key path in `value`, e.g. `mainStocksPriceBoard.priceBoard.code`
+/// or `commonPr.publicRelations[0].title`.
+pub fn list_keys(value: &Value) -> Vec<String> {
+    let mut keys = Vec::new();
+    collect_keys(value, "", &mut keys);
+    keys
+}
+
+fn collect_keys(value: &Value, prefix: &str, keys: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, val) in map {
+                let new_prefix = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                keys.push(new_prefix.clone());
+                collect_keys(val, &new_prefix, keys);
+            }
+        }
+        Value::Array(arr) => {
+            for (i, val) in arr.iter().enumerate() {
+                let new_prefix = format!("{}[{}]", prefix, i);
+                collect_keys(val, &new_prefix, keys);
+            }
+        }
+        _ => {}
+    }
+}
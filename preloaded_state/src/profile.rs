@@ -0,0 +1,152 @@
+//! Company profile: the label/value table in `mainStocksProfile.items` plus
+//! the name, market and industry from the price board.
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::board::{lenient_records, lenient_string};
+use crate::{parse_decimal, parse_record_date, PriceBoard};
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct MainStocksProfile {
+    #[serde(deserialize_with = "lenient_records")]
+    items: Vec<ProfileRecord>,
+}
+
+/// A row of the profile table: its label and the value displayed.
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct ProfileRecord {
+    title: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    value: Option<String>,
+}
+
+/// One row of the profile table, as displayed.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ProfileItem {
+    pub label: String,
+    pub value: String,
+}
+
+/// `priceBoard.industry` as a typed value.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct IndustryInfo {
+    pub name: Option<String>,
+    pub link: Option<String>,
+}
+
+/// What a company does and its key facts. Fields are filled from the profile
+/// rows they are named after; `items` keeps every row as displayed.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct CompanyProfile {
+    pub name: Option<String>,
+    pub english_name: Option<String>,
+    pub market: Option<String>,
+    pub industry: IndustryInfo,
+    /// 特色: a one-line description of the business.
+    pub description: Option<String>,
+    /// 連結事業: business segments and their share of sales.
+    pub segments: Option<String>,
+    /// 業種分類: the sector classification in the profile table.
+    pub sector: Option<String>,
+    pub headquarters: Option<String>,
+    pub representative: Option<String>,
+    pub founded: Option<NaiveDate>,
+    pub listing_date: Option<NaiveDate>,
+    pub fiscal_year_end: Option<String>,
+    pub share_unit: Option<Decimal>,
+    pub employees_consolidated: Option<Decimal>,
+    pub employees_non_consolidated: Option<Decimal>,
+    pub average_age: Option<Decimal>,
+    pub average_annual_income: Option<Decimal>,
+    pub items: Vec<ProfileItem>,
+}
+
+/// Extracts the company profile, or `None` when the page has neither a profile
+/// section nor a stock price board.
+pub fn extract_company_profile(state: &Value) -> Option<CompanyProfile> {
+    let section = state.get("mainStocksProfile");
+    let board = state.pointer("/mainStocksPriceBoard/priceBoard");
+    if section.is_none() && board.is_none() {
+        return None;
+    }
+
+    let board = board
+        .and_then(|b| PriceBoard::deserialize(b).ok())
+        .unwrap_or_default();
+    let industry = board.industry.unwrap_or_default();
+    let mut profile = CompanyProfile {
+        name: board.name,
+        market: board.market_name,
+        industry: IndustryInfo {
+            name: industry.industry_name,
+            link: industry.industry_items_link,
+        },
+        ..CompanyProfile::default()
+    };
+
+    let records = section
+        .and_then(|s| MainStocksProfile::deserialize(s).ok())
+        .unwrap_or_default()
+        .items;
+    for record in records {
+        let (Some(label), Some(value)) = (record.title, record.value) else {
+            continue;
+        };
+        let value = value.trim().to_string();
+        if value.is_empty() {
+            continue;
+        }
+        apply_item(&mut profile, &normalize_label(&label), &value);
+        profile.items.push(ProfileItem { label, value });
+    }
+    Some(profile)
+}
+
+/// Drops spaces and folds full-width parentheses so `"従業員数（連結）"` and
+/// `"従業員数 (連結)"` match alike.
+fn normalize_label(label: &str) -> String {
+    label
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c {
+            '（' => '(',
+            '）' => ')',
+            _ => c,
+        })
+        .collect()
+}
+
+fn apply_item(profile: &mut CompanyProfile, label: &str, value: &str) {
+    let text = || Some(value.to_string());
+    match label {
+        "特色" => profile.description = text(),
+        "連結事業" => profile.segments = text(),
+        "業種分類" => profile.sector = text(),
+        "英文社名" => profile.english_name = text(),
+        "本社所在地" => profile.headquarters = text(),
+        "代表者名" => profile.representative = text(),
+        "設立年月日" => profile.founded = parse_record_date(value),
+        "上場年月日" => profile.listing_date = parse_record_date(value),
+        "決算" => profile.fiscal_year_end = text(),
+        "単元株数" => profile.share_unit = leading_number(value),
+        "従業員数(連結)" => profile.employees_consolidated = leading_number(value),
+        "従業員数(単独)" => profile.employees_non_consolidated = leading_number(value),
+        "平均年齢" => profile.average_age = leading_number(value),
+        "平均年収" => profile.average_annual_income = leading_number(value),
+        _ => {}
+    }
+}
+
+/// Parses the number at the start of a value with a unit, such as
+/// `"2,383人"`, `"43.2歳"` or `"100株"`.
+fn leading_number(value: &str) -> Option<Decimal> {
+    let end = value
+        .find(|c: char| !(c.is_ascii_digit() || c == ',' || c == '.'))
+        .unwrap_or(value.len());
+    parse_decimal(&value[..end])
+}
@@ -0,0 +1,170 @@
+//! Attention (access) rankings from `stockAttentionRanking` and the related
+//! tickers from `mainRelatedItem.relatedItems`.
+
+use chrono::{DateTime, FixedOffset};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::board::{lenient_records, lenient_string};
+use crate::parse_decimal;
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct RankingPeriod {
+    data: RankingData,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct RankingData {
+    base_date_time: Option<String>,
+    update_time: Option<String>,
+    #[serde(deserialize_with = "lenient_records")]
+    attention_rankings: Vec<RankingRecord>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct RankingRecord {
+    rank: Option<u32>,
+    stock_code: Option<String>,
+    display_name: Option<String>,
+    market_name: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    access_count: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    access_count_ratio: Option<String>,
+    attention_level: Option<u32>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct MainRelatedItem {
+    #[serde(deserialize_with = "lenient_records")]
+    related_items: Vec<RelatedRecord>,
+}
+
+/// A related ticker. The captured page's list is empty, so items are read
+/// with the keys of the attention ranking's stocks.
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct RelatedRecord {
+    stock_code: Option<String>,
+    display_name: Option<String>,
+    market_name: Option<String>,
+}
+
+/// A stock in an attention ranking.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RankedStock {
+    pub rank: u32,
+    pub code: String,
+    pub name: Option<String>,
+    pub market: Option<String>,
+    /// Page views in the ranking period.
+    pub access_count: Option<Decimal>,
+    /// Page views relative to the stock's usual level.
+    pub access_count_ratio: Option<Decimal>,
+    /// 0-100 score shown as the attention gauge.
+    pub attention_level: Option<u32>,
+}
+
+/// The attention ranking for one period. Slots Yahoo leaves empty are dropped.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct AttentionRanking {
+    pub base_time: Option<DateTime<FixedOffset>>,
+    pub updated: Option<DateTime<FixedOffset>>,
+    pub stocks: Vec<RankedStock>,
+}
+
+/// A ticker listed under "related stocks".
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RelatedItem {
+    pub code: String,
+    pub name: Option<String>,
+    pub market: Option<String>,
+}
+
+/// The tickers a quote page links to as related or trending.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct Related {
+    pub related_items: Vec<RelatedItem>,
+    /// Today's attention ranking.
+    pub one_day: Option<AttentionRanking>,
+    /// The ranking over the last five days.
+    pub five_day: Option<AttentionRanking>,
+}
+
+impl Related {
+    /// Every code mentioned, related items first, without duplicates.
+    pub fn codes(&self) -> Vec<String> {
+        let ranked = [&self.one_day, &self.five_day]
+            .into_iter()
+            .flatten()
+            .flat_map(|ranking| ranking.stocks.iter().map(|s| &s.code));
+        let mut codes: Vec<String> = Vec::new();
+        for code in self.related_items.iter().map(|item| &item.code).chain(ranked) {
+            if !codes.contains(code) {
+                codes.push(code.clone());
+            }
+        }
+        codes
+    }
+}
+
+/// Extracts related items and attention rankings, or `None` when the page has
+/// neither section.
+pub fn extract_related(state: &Value) -> Option<Related> {
+    let related_section = state.get("mainRelatedItem");
+    let ranking_section = state.get("stockAttentionRanking");
+    if related_section.is_none() && ranking_section.is_none() {
+        return None;
+    }
+
+    let related_items = related_section
+        .and_then(|s| MainRelatedItem::deserialize(s).ok())
+        .unwrap_or_default()
+        .related_items
+        .into_iter()
+        .filter_map(|record| {
+            Some(RelatedItem {
+                code: record.stock_code.filter(|c| !c.is_empty())?,
+                name: record.display_name,
+                market: record.market_name,
+            })
+        })
+        .collect();
+
+    let ranking = |period: &str| {
+        let period = RankingPeriod::deserialize(ranking_section?.get(period)?).ok()?;
+        let time = |raw: Option<String>| raw.and_then(|r| DateTime::parse_from_rfc3339(&r).ok());
+        let stocks = period
+            .data
+            .attention_rankings
+            .into_iter()
+            .filter_map(|record| {
+                Some(RankedStock {
+                    rank: record.rank?,
+                    code: record.stock_code.filter(|c| !c.is_empty())?,
+                    name: record.display_name,
+                    market: record.market_name,
+                    access_count: record.access_count.as_deref().and_then(parse_decimal),
+                    access_count_ratio: record.access_count_ratio.as_deref().and_then(parse_decimal),
+                    attention_level: record.attention_level,
+                })
+            })
+            .collect();
+        Some(AttentionRanking {
+            base_time: time(period.data.base_date_time),
+            updated: time(period.data.update_time),
+            stocks,
+        })
+    };
+
+    Some(Related {
+        related_items,
+        one_day: ranking("oneDay"),
+        five_day: ranking("fiveDay"),
+    })
+}
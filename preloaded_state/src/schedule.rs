@@ -0,0 +1,193 @@
+//! Pacing of upstream fetches: a cap on how many run at once, a token bucket
+//! per host, a random delay before each request and backoff between retries.
+//!
+//! Runtime-agnostic: the caller supplies the clock and sleep through [`Clock`],
+//! from the Workers runtime on wasm32 or tokio natively.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::future::Future;
+
+use futures::future::LocalBoxFuture;
+use futures::stream::{self, StreamExt};
+
+use crate::{PageFetcher, UpstreamError};
+
+/// Longest wait before a retry; a longer `Retry-After` ends the retries instead.
+pub const MAX_RETRY_DELAY_MILLIS: u64 = 10_000;
+
+/// Time source and timer of the runtime the scheduler runs on.
+pub trait Clock {
+    /// Milliseconds since an arbitrary fixed point.
+    fn now_millis(&self) -> u64;
+    fn sleep(&self, millis: u64) -> LocalBoxFuture<'static, ()>;
+}
+
+/// Limits applied to upstream fetches.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SchedulerConfig {
+    /// Most fetches in flight at once within a batch.
+    pub max_concurrency: usize,
+    /// Sustained requests per second allowed to one host; 0 disables the limit.
+    pub requests_per_second: f64,
+    /// Requests a host may receive back to back before the rate applies.
+    pub burst: u32,
+    /// Upper bound of the random delay added before each request.
+    pub max_jitter_millis: u64,
+    /// Retries after a transient failure; 0 disables them.
+    pub max_retries: u32,
+    /// Wait before the first retry, doubled for each one after it.
+    pub retry_base_millis: u64,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        SchedulerConfig {
+            max_concurrency: 6,
+            requests_per_second: 4.0,
+            burst: 4,
+            max_jitter_millis: 150,
+            max_retries: 2,
+            retry_base_millis: 500,
+        }
+    }
+}
+
+struct TokenBucket {
+    tokens: f64,
+    updated_at: u64,
+}
+
+/// Paces fetches according to a [`SchedulerConfig`].
+pub struct FetchScheduler<C: Clock> {
+    config: SchedulerConfig,
+    clock: C,
+    buckets: RefCell<HashMap<String, TokenBucket>>,
+    rng_state: Cell<u64>,
+}
+
+impl<C: Clock> FetchScheduler<C> {
+    pub fn new(config: SchedulerConfig, clock: C) -> Self {
+        let seed = clock.now_millis() | 1;
+        FetchScheduler {
+            config,
+            clock,
+            buckets: RefCell::new(HashMap::new()),
+            rng_state: Cell::new(seed),
+        }
+    }
+
+    pub fn config(&self) -> &SchedulerConfig {
+        &self.config
+    }
+
+    /// Runs `jobs` with at most `max_concurrency` in flight and returns their
+    /// outputs in the order given. Jobs pace their own requests with [`throttle`].
+    ///
+    /// [`throttle`]: FetchScheduler::throttle
+    pub async fn run<I, F, T>(&self, jobs: I) -> Vec<T>
+    where
+        I: IntoIterator<Item = F>,
+        F: Future<Output = T>,
+    {
+        stream::iter(jobs)
+            .buffered(self.config.max_concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    /// Waits until a request to `host` is allowed: for a token from the host's
+    /// bucket, then for a random jitter.
+    pub async fn throttle(&self, host: &str) {
+        let wait = self.take_token(host);
+        let jitter = self.jitter();
+        if wait + jitter > 0 {
+            self.clock.sleep(wait + jitter).await;
+        }
+    }
+
+    /// Fetches `url` with `fetcher`, throttled per host and retried like [`retry`].
+    ///
+    /// [`retry`]: FetchScheduler::retry
+    pub async fn fetch<F: PageFetcher>(&self, fetcher: &F, url: &str) -> Result<String, UpstreamError> {
+        self.retry(url_host(url), || fetcher.fetch(url)).await
+    }
+
+    /// Runs `fetch` against `host`, throttled, retrying transient failures
+    /// with exponential backoff. Returns the last error once the retries are
+    /// spent, or at once for errors that are not transient.
+    pub async fn retry<F, Fut, T>(&self, host: &str, mut fetch: F) -> Result<T, UpstreamError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, UpstreamError>>,
+    {
+        let mut attempt = 0;
+        loop {
+            self.throttle(host).await;
+            let error = match fetch().await {
+                Err(error) if error.is_transient() && attempt < self.config.max_retries => error,
+                result => return result,
+            };
+            let delay = self.backoff(attempt, &error);
+            if delay > MAX_RETRY_DELAY_MILLIS {
+                return Err(error);
+            }
+            self.clock.sleep(delay + self.jitter()).await;
+            attempt += 1;
+        }
+    }
+
+    /// The wait before retry number `attempt` (from 0): the base delay doubled
+    /// per attempt, or the wait the upstream asked for if longer.
+    fn backoff(&self, attempt: u32, error: &UpstreamError) -> u64 {
+        let exponential = self.config.retry_base_millis.saturating_mul(1 << attempt.min(16));
+        exponential.max(error.retry_after_millis().unwrap_or(0))
+    }
+
+    /// Takes a token from `host`'s bucket, borrowing one that has not been
+    /// refilled yet if needed, and returns how long to wait before it is.
+    fn take_token(&self, host: &str) -> u64 {
+        let rate = self.config.requests_per_second;
+        if rate <= 0.0 {
+            return 0;
+        }
+        let now = self.clock.now_millis();
+        let capacity = f64::from(self.config.burst.max(1));
+        let mut buckets = self.buckets.borrow_mut();
+        let bucket = buckets.entry(host.to_string()).or_insert(TokenBucket {
+            tokens: capacity,
+            updated_at: now,
+        });
+
+        let elapsed = now.saturating_sub(bucket.updated_at) as f64 / 1000.0;
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(capacity);
+        bucket.updated_at = now;
+        bucket.tokens -= 1.0;
+
+        if bucket.tokens >= 0.0 {
+            0
+        } else {
+            (-bucket.tokens / rate * 1000.0).ceil() as u64
+        }
+    }
+
+    /// A pseudo-random delay in `0..=max_jitter_millis` (xorshift).
+    fn jitter(&self) -> u64 {
+        if self.config.max_jitter_millis == 0 {
+            return 0;
+        }
+        let mut x = self.rng_state.get();
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.rng_state.set(x);
+        x % (self.config.max_jitter_millis + 1)
+    }
+}
+
+/// The host of a URL, used as the rate limit key.
+pub fn url_host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+    authority.rsplit_once('@').map_or(authority, |(_, host)| host)
+}
@@ -0,0 +1,89 @@
+//! Community sentiment from `stockPredictions`: this week's up/down vote split,
+//! the voting window and the reasons voters picked.
+
+use chrono::{DateTime, FixedOffset};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::board::lenient_string;
+use crate::parse_decimal;
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct StockPredictions {
+    this_week_user_prediction_data: Option<PredictionData>,
+    voting_start_date_time: Option<String>,
+    voting_deadline_date_time: Option<String>,
+    result_announcement_date_time: Option<String>,
+    next_voting_start_date_time: Option<String>,
+    survey_status: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct PredictionData {
+    has_enough_votes: bool,
+    total_votes: Option<u64>,
+    #[serde(deserialize_with = "lenient_string")]
+    plus_votes_percent: Option<String>,
+    #[serde(deserialize_with = "lenient_string")]
+    minus_votes_percent: Option<String>,
+    plus_prediction_reason_list: Vec<String>,
+    minus_prediction_reason_list: Vec<String>,
+    update_date_time: Option<String>,
+}
+
+/// The voting window of the weekly survey.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct VotingWindow {
+    pub start: Option<DateTime<FixedOffset>>,
+    pub deadline: Option<DateTime<FixedOffset>>,
+    pub result_announcement: Option<DateTime<FixedOffset>>,
+    pub next_start: Option<DateTime<FixedOffset>>,
+    /// `"open"` while votes are accepted.
+    pub status: Option<String>,
+}
+
+/// This week's community vote on whether a stock will rise.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SentimentSnapshot {
+    /// Share of votes expecting a rise, in percent.
+    pub plus_votes_percent: Option<Decimal>,
+    pub minus_votes_percent: Option<Decimal>,
+    pub total_votes: Option<u64>,
+    pub has_enough_votes: bool,
+    /// Set when Yahoo considers the sample too small to show the split
+    /// (`hasEnoughVotes: false`); the percentages should not be relied on.
+    pub thin_sample: bool,
+    pub plus_reasons: Vec<String>,
+    pub minus_reasons: Vec<String>,
+    pub voting: VotingWindow,
+    pub updated: Option<DateTime<FixedOffset>>,
+}
+
+/// Extracts this week's sentiment, or `None` when the page has no
+/// `stockPredictions` survey (index, currency and fund pages).
+pub fn extract_sentiment(state: &Value) -> Option<SentimentSnapshot> {
+    let predictions = StockPredictions::deserialize(state.get("stockPredictions")?).ok()?;
+    let week = predictions.this_week_user_prediction_data?;
+
+    let time = |raw: Option<String>| raw.and_then(|r| DateTime::parse_from_rfc3339(&r).ok());
+    Some(SentimentSnapshot {
+        plus_votes_percent: week.plus_votes_percent.as_deref().and_then(parse_decimal),
+        minus_votes_percent: week.minus_votes_percent.as_deref().and_then(parse_decimal),
+        total_votes: week.total_votes,
+        has_enough_votes: week.has_enough_votes,
+        thin_sample: !week.has_enough_votes,
+        plus_reasons: week.plus_prediction_reason_list,
+        minus_reasons: week.minus_prediction_reason_list,
+        voting: VotingWindow {
+            start: time(predictions.voting_start_date_time),
+            deadline: time(predictions.voting_deadline_date_time),
+            result_announcement: time(predictions.result_announcement_date_time),
+            next_start: time(predictions.next_voting_start_date_time),
+            status: predictions.survey_status,
+        },
+        updated: time(week.update_date_time),
+    })
+}
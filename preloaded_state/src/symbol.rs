@@ -0,0 +1,228 @@
+//! Typed ticker symbols as accepted in the `code` parameter, with the page URL
+//! and board code each one maps to.
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono_tz::Tz;
+
+use crate::{exchange_timezone, SymbolError, TradingCalendar};
+
+const QUOTE_BASE_URL: &str = "https://finance.yahoo.co.jp/quote/";
+
+/// The Japanese exchange a stock is quoted on, from its code suffix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Market {
+    /// `.T`, the default for codes without a suffix.
+    Tokyo,
+    /// `.O`, used for domestic index codes such as `998407.O`.
+    Osaka,
+    /// `.N`, Nagoya Stock Exchange.
+    Nagoya,
+    /// `.F`, Fukuoka Stock Exchange.
+    Fukuoka,
+    /// `.S`, Sapporo Securities Exchange.
+    Sapporo,
+}
+
+impl Market {
+    fn from_suffix(suffix: &str) -> Option<Market> {
+        match suffix {
+            "T" => Some(Market::Tokyo),
+            "O" => Some(Market::Osaka),
+            "N" => Some(Market::Nagoya),
+            "F" => Some(Market::Fukuoka),
+            "S" => Some(Market::Sapporo),
+            _ => None,
+        }
+    }
+
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Market::Tokyo => "T",
+            Market::Osaka => "O",
+            Market::Nagoya => "N",
+            Market::Fukuoka => "F",
+            Market::Sapporo => "S",
+        }
+    }
+}
+
+/// A code the worker knows how to look up.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Symbol {
+    /// A domestic stock, ETF or REIT: `7203`, `7203.T`, `9643.N`, `1306`, `130A`.
+    JpStock { code: String, market: Market },
+    /// An overseas (`^DJI`) or domestic (`998407.O`) index, kept as written.
+    /// A bare 6-digit code is read as Tokyo (`998405` -> `998405.T`).
+    Index(String),
+    /// A currency pair's `=X` page (`USDJPY=X`), holding the pair.
+    CurrencyX(String),
+    /// A currency pair's `=FX` page (`USDJPY=FX`), holding the pair.
+    CurrencyFx(String),
+    /// An investment trust by its 8-character fund code (`0331418A`).
+    Fund(String),
+    /// A US-listed stock by ticker (`AAPL`).
+    UsStock(String),
+}
+
+impl Symbol {
+    /// The spelling Yahoo uses in page URLs (`7203.T`, `^DJI`, `USDJPY=X`).
+    pub fn canonical(&self) -> String {
+        match self {
+            Symbol::JpStock { code, market } => format!("{}.{}", code, market.suffix()),
+            Symbol::Index(code) | Symbol::Fund(code) | Symbol::UsStock(code) => code.clone(),
+            Symbol::CurrencyX(pair) => format!("{}=X", pair),
+            Symbol::CurrencyFx(pair) => format!("{}=FX", pair),
+        }
+    }
+
+    /// The quote page URL.
+    pub fn quote_url(&self) -> String {
+        format!("{}{}/", QUOTE_BASE_URL, self.canonical())
+    }
+
+    /// The code without its market or page suffix, as price boards usually
+    /// report it (`7203.T` -> `7203`, `USDJPY=X` -> `USDJPY`).
+    pub fn board_code(&self) -> &str {
+        match self {
+            Symbol::JpStock { code, .. } | Symbol::Fund(code) | Symbol::UsStock(code) => code,
+            Symbol::CurrencyX(pair) | Symbol::CurrencyFx(pair) => pair,
+            Symbol::Index(code) => code.split_once('.').map_or(code.as_str(), |(bare, _)| bare),
+        }
+    }
+
+    /// Whether a code read from a price board refers to this symbol. Boards
+    /// report codes with or without the suffix depending on the page.
+    pub fn matches_board_code(&self, board_code: &str) -> bool {
+        let board_code = board_code.trim();
+        board_code.eq_ignore_ascii_case(self.board_code()) || board_code.eq_ignore_ascii_case(&self.canonical())
+    }
+
+    /// The timezone the symbol's page reports times in.
+    pub fn timezone(&self) -> Tz {
+        match self {
+            Symbol::UsStock(_) => chrono_tz::America::New_York,
+            _ => exchange_timezone(&self.canonical()),
+        }
+    }
+
+    /// The days the symbol trades on. Only domestic listings and indices
+    /// follow TSE holidays; currencies keep trading through them.
+    pub fn calendar(&self) -> TradingCalendar {
+        match self {
+            Symbol::JpStock { .. } => TradingCalendar::Tse,
+            Symbol::Index(code) if !code.starts_with('^') => TradingCalendar::Tse,
+            _ => TradingCalendar::Weekdays,
+        }
+    }
+}
+
+/// The timezone of a code's page: that of its `Symbol`, or of the bare code
+/// when it does not parse.
+pub(crate) fn code_timezone(code: &str) -> Tz {
+    code.parse::<Symbol>().map_or_else(|_| exchange_timezone(code), |symbol| symbol.timezone())
+}
+
+/// The trading calendar of a code's `Symbol`; weekdays when it does not parse.
+pub(crate) fn code_calendar(code: &str) -> TradingCalendar {
+    code.parse::<Symbol>().map_or(TradingCalendar::Weekdays, |symbol| symbol.calendar())
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.canonical())
+    }
+}
+
+impl FromStr for Symbol {
+    type Err = SymbolError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let code = input.trim().to_ascii_uppercase();
+        let malformed = |reason| SymbolError::Malformed { input: input.trim().to_string(), reason };
+        if code.is_empty() {
+            return Err(SymbolError::Empty);
+        }
+
+        if let Some(name) = code.strip_prefix('^') {
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-') {
+                return Err(malformed("index codes are '^' followed by letters or digits"));
+            }
+            return Ok(Symbol::Index(code));
+        }
+        if let Some(pair) = code.strip_suffix("=FX") {
+            return currency_pair(pair).map(Symbol::CurrencyFx).ok_or_else(|| malformed("invalid currency pair"));
+        }
+        if let Some(pair) = code.strip_suffix("=X") {
+            return currency_pair(pair).map(Symbol::CurrencyX).ok_or_else(|| malformed("invalid currency pair"));
+        }
+
+        if let Some((bare, suffix)) = code.rsplit_once('.') {
+            let market = Market::from_suffix(suffix).ok_or_else(|| SymbolError::UnknownMarket {
+                input: input.trim().to_string(),
+                suffix: suffix.to_string(),
+            })?;
+            if is_domestic_index_code(bare) {
+                return Ok(Symbol::Index(code));
+            }
+            if !is_stock_code(bare) {
+                return Err(malformed("stock codes are 4 characters, such as 7203 or 130A"));
+            }
+            return Ok(Symbol::JpStock { code: bare.to_string(), market });
+        }
+
+        if is_stock_code(&code) {
+            Ok(Symbol::JpStock { code, market: Market::Tokyo })
+        } else if is_domestic_index_code(&code) {
+            Ok(Symbol::Index(format!("{}.{}", code, Market::Tokyo.suffix())))
+        } else if is_fund_code(&code) {
+            Ok(Symbol::Fund(code))
+        } else if is_us_ticker(&code) {
+            Ok(Symbol::UsStock(code))
+        } else {
+            Err(malformed("not a stock, index, currency, fund or US ticker code"))
+        }
+    }
+}
+
+/// The distinct symbols among `codes`, in order of first appearance, so a
+/// batch such as `7203.T,7203,7203.t` needs one fetch. Codes that do not
+/// parse are skipped.
+pub fn distinct_symbols<S: AsRef<str>>(codes: &[S]) -> Vec<Symbol> {
+    let mut symbols: Vec<Symbol> = Vec::new();
+    for symbol in codes.iter().filter_map(|code| code.as_ref().parse::<Symbol>().ok()) {
+        if !symbols.contains(&symbol) {
+            symbols.push(symbol);
+        }
+    }
+    symbols
+}
+
+fn currency_pair(pair: &str) -> Option<String> {
+    (!pair.is_empty() && pair.chars().all(|c| c.is_ascii_alphanumeric())).then(|| pair.to_string())
+}
+
+/// Stock, ETF and REIT codes are 4 characters. Codes issued since 2024 may
+/// use a letter in the second or fourth place (`130A`, `2B3C`).
+fn is_stock_code(code: &str) -> bool {
+    let bytes = code.as_bytes();
+    bytes.len() == 4
+        && bytes[0].is_ascii_digit()
+        && bytes[2].is_ascii_digit()
+        && bytes[1].is_ascii_alphanumeric()
+        && bytes[3].is_ascii_alphanumeric()
+}
+
+/// Domestic indices use 6-digit codes (`998407.O`, `998405.T`).
+fn is_domestic_index_code(code: &str) -> bool {
+    code.len() == 6 && code.chars().all(|c| c.is_ascii_digit())
+}
+
+fn is_fund_code(code: &str) -> bool {
+    code.len() == 8 && code.chars().all(|c| c.is_ascii_alphanumeric()) && code.chars().any(|c| c.is_ascii_digit())
+}
+
+fn is_us_ticker(code: &str) -> bool {
+    (1..=5).contains(&code.len()) && code.chars().all(|c| c.is_ascii_alphabetic())
+}
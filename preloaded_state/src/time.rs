@@ -0,0 +1,129 @@
+//! Resolution of the update times shown on quote pages (`"15:30"`, `"10/16"`,
+//! `"2025-10-16T15:30:00+09:00"`) into instants in the exchange's timezone.
+
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+
+use crate::calendar::is_tse_holiday;
+
+/// How far in the future a time-of-day may lie before it is read as yesterday's,
+/// to absorb clock skew between us and Yahoo.
+const FUTURE_TOLERANCE_MINUTES: i64 = 5;
+
+/// Returns the timezone a code's page reports its times in.
+///
+/// Tokyo for domestic stocks, indices, funds and currencies; the local exchange
+/// time for overseas indices, defaulting to New York for `^` codes.
+pub fn exchange_timezone(code: &str) -> Tz {
+    match code {
+        "^HSI" => chrono_tz::Asia::Hong_Kong,
+        "^FTSE" => chrono_tz::Europe::London,
+        "^GDAXI" | "^STOXX50E" => chrono_tz::Europe::Berlin,
+        "^FCHI" => chrono_tz::Europe::Paris,
+        c if c.starts_with('^') => chrono_tz::America::New_York,
+        _ => chrono_tz::Asia::Tokyo,
+    }
+}
+
+/// The days a market trades on, used to complete a bare time of day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TradingCalendar {
+    /// Weekdays that are not TSE holidays: domestic stocks, ETFs, REITs and
+    /// indices.
+    Tse,
+    /// Every weekday: currencies, funds, US stocks and overseas indices.
+    Weekdays,
+}
+
+/// Parses an update time as shown on a quote page.
+///
+/// RFC 3339 strings keep their own offset. Otherwise the value is read in `tz`
+/// and missing parts are filled in from `fetched_at`: a bare `"15:30"` is the
+/// latest day of `calendar` on which that time had already passed, and a bare
+/// `"10/16"` is the latest such date not after the fetch (at midnight).
+pub fn parse_market_time(
+    raw: &str,
+    tz: Tz,
+    calendar: TradingCalendar,
+    fetched_at: DateTime<Utc>,
+) -> Option<DateTime<FixedOffset>> {
+    let raw = raw.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(raw) {
+        return Some(dt);
+    }
+
+    let now = fetched_at.with_timezone(&tz).naive_local();
+    let mut date = None;
+    let mut time = None;
+    for token in raw.split_whitespace() {
+        if token.contains(':') {
+            time = Some(parse_time(token)?);
+        } else {
+            date = Some(parse_date(token, now.date())?);
+        }
+    }
+
+    let local = match (date, time) {
+        (Some(date), Some(time)) => date.and_time(time),
+        (Some(date), None) => date.and_time(NaiveTime::MIN),
+        (None, Some(time)) => latest_trading_datetime(time, now, calendar),
+        (None, None) => return None,
+    };
+    tz.from_local_datetime(&local).earliest().map(|dt| dt.fixed_offset())
+}
+
+fn parse_time(token: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(token, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(token, "%H:%M"))
+        .ok()
+}
+
+/// Parses `2025/10/16`, `2025-10-16`, `25/10/07` or a year-less `10/16`.
+fn parse_date(token: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let parts: Vec<&str> = token.split(['/', '-']).collect();
+    let numbers: Vec<u32> = parts.iter().map(|p| p.parse().ok()).collect::<Option<_>>()?;
+    match numbers[..] {
+        [y, m, d] if parts[0].len() == 4 => NaiveDate::from_ymd_opt(y as i32, m, d),
+        [y, m, d] if parts[0].len() == 2 => NaiveDate::from_ymd_opt(2000 + y as i32, m, d),
+        [m, d] => {
+            let this_year = NaiveDate::from_ymd_opt(today.year(), m, d)?;
+            if this_year > today + Duration::days(1) {
+                NaiveDate::from_ymd_opt(today.year() - 1, m, d)
+            } else {
+                Some(this_year)
+            }
+        }
+        _ => None,
+    }
+}
+
+fn latest_trading_datetime(time: NaiveTime, now: NaiveDateTime, calendar: TradingCalendar) -> NaiveDateTime {
+    let closed = |date: NaiveDate| match calendar {
+        TradingCalendar::Tse => is_tse_holiday(date),
+        TradingCalendar::Weekdays => matches!(date.weekday(), Weekday::Sat | Weekday::Sun),
+    };
+    let mut candidate = now.date().and_time(time);
+    if candidate > now + Duration::minutes(FUTURE_TOLERANCE_MINUTES) {
+        candidate -= Duration::days(1);
+    }
+    while closed(candidate.date()) {
+        candidate -= Duration::days(1);
+    }
+    candidate
+}
+
+/// Parses the date of a daily record (`baseDatetime`, `yearHighPriceDate`, ...):
+/// `"2025-04-03T00:00:00+09:00"`, `"2025/04/03"`, `"25/04/03"`, `"2025-04-03"`
+/// or `"2025年4月3日"`. RFC 3339 values keep the calendar date of their own offset.
+pub fn parse_record_date(raw: &str) -> Option<NaiveDate> {
+    let raw = raw.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(raw) {
+        return Some(dt.date_naive());
+    }
+    if raw.find('/') == Some(2) {
+        return NaiveDate::parse_from_str(raw, "%y/%m/%d").ok();
+    }
+    ["%Y/%m/%d", "%Y-%m-%d", "%Y年%m月%d日"]
+        .iter()
+        .find_map(|fmt| NaiveDate::parse_from_str(raw, fmt).ok())
+}
@@ -0,0 +1,56 @@
+use preloaded_state::{extract_preloaded_state, FundPrice, PriceBoard, Quote, Symbol};
+use serde::Deserialize;
+
+const NAGOYA_PAGE: &str = include_str!("../../fixtures/9643.N.html");
+const FUKUOKA_PAGE: &str = include_str!("../../fixtures/8596.F.html");
+const SAPPORO_PAGE: &str = include_str!("../../fixtures/9534.S.html");
+const ETF_PAGE: &str = include_str!("../../fixtures/1306.T.html");
+const REIT_PAGE: &str = include_str!("../../fixtures/8951.T.html");
+const FUND_PAGE: &str = include_str!("../../fixtures/0331418A.html");
+
+fn stock_quote(page: &str) -> (PriceBoard, Quote) {
+    let state = extract_preloaded_state(page).unwrap();
+    let board = PriceBoard::deserialize(&state["mainStocksPriceBoard"]["priceBoard"]).unwrap();
+    (board.clone(), Quote::from(board))
+}
+
+#[test]
+fn regional_market_boards_match_their_symbols() {
+    for (code, page, market) in [
+        ("9643.N", NAGOYA_PAGE, "名証メイン"),
+        ("8596.F", FUKUOKA_PAGE, "福証"),
+        ("9534.S", SAPPORO_PAGE, "札証"),
+    ] {
+        let symbol: Symbol = code.parse().unwrap();
+        let (board, quote) = stock_quote(page);
+        assert!(symbol.matches_board_code(&quote.code), "{}", code);
+        assert_eq!(board.code_with_market_extension.as_deref(), Some(code));
+        assert_eq!(board.market_name.as_deref(), Some(market));
+    }
+}
+
+#[test]
+fn etf_and_reit_use_the_stock_board() {
+    let (board, quote) = stock_quote(ETF_PAGE);
+    assert_eq!(board.type_detail.as_deref(), Some("ETF"));
+    assert!("1306".parse::<Symbol>().unwrap().matches_board_code(&quote.code));
+    assert_eq!(quote.price.as_deref(), Some("3,254"));
+
+    let (board, quote) = stock_quote(REIT_PAGE);
+    assert_eq!(board.type_detail.as_deref(), Some("REIT"));
+    assert_eq!(quote.price.as_deref(), Some("136,800"));
+}
+
+#[test]
+fn fund_board_decodes_to_a_quote() {
+    let state = extract_preloaded_state(FUND_PAGE).unwrap();
+    let board = FundPrice::deserialize(&state["mainFundPriceBoard"]["fundPrices"]).unwrap();
+    assert_eq!(board.net_asset_balance.as_deref(), Some("7,816,035"));
+
+    let quote = Quote::from(board);
+    assert!("0331418A".parse::<Symbol>().unwrap().matches_board_code(&quote.code));
+    assert_eq!(quote.price.as_deref(), Some("32,179"));
+    assert_eq!(quote.price_change.as_deref(), Some("+184"));
+    assert_eq!(quote.price_change_rate.as_deref(), Some("+0.58"));
+    assert_eq!(quote.update_time.as_deref(), Some("2025-11-06T00:00:00+09:00"));
+}
@@ -0,0 +1,120 @@
+use chrono::{DateTime, Datelike, NaiveDate, Utc, Weekday};
+use preloaded_state::{
+    cache_key, cache_ttl, is_tse_holiday, tse_holiday, tse_session, CachedPage, MemoryCache, Symbol, TseSession,
+    CLOSED_TTL_SECONDS, HOLIDAY_TTL_SECONDS, TRADING_TTL_SECONDS, TSE_CALENDAR_YEARS,
+};
+
+fn utc(s: &str) -> DateTime<Utc> {
+    s.parse().unwrap()
+}
+
+fn symbol(code: &str) -> Symbol {
+    code.parse().unwrap()
+}
+
+#[test]
+fn tse_sessions() {
+    // Thursday 2025-10-16
+    assert_eq!(tse_session(utc("2025-10-15T23:59:00Z")), TseSession::Closed); // 08:59 JST
+    assert_eq!(tse_session(utc("2025-10-16T00:00:00Z")), TseSession::Trading); // 09:00
+    assert_eq!(tse_session(utc("2025-10-16T03:00:00Z")), TseSession::Trading); // 12:00, lunch
+    assert_eq!(tse_session(utc("2025-10-16T06:30:00Z")), TseSession::Closed); // 15:30
+    assert_eq!(tse_session(utc("2025-10-18T02:00:00Z")), TseSession::Holiday); // Saturday
+    assert_eq!(tse_session(utc("2025-11-03T02:00:00Z")), TseSession::Holiday); // Culture Day
+}
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+#[test]
+fn holidays() {
+    assert!(is_tse_holiday(date(2026, 1, 2)));
+    assert!(is_tse_holiday(date(2025, 12, 31)));
+    assert!(is_tse_holiday(date(2026, 9, 22)));
+    assert!(!is_tse_holiday(date(2026, 1, 5)));
+}
+
+#[test]
+fn weekday_holidays_match_the_published_calendar() {
+    // As published by the exchange for 2025 and 2026
+    let published = [
+        (2025, 1, 13), (2025, 2, 11), (2025, 2, 24), (2025, 3, 20), (2025, 4, 29), (2025, 5, 5), (2025, 5, 6),
+        (2025, 7, 21), (2025, 8, 11), (2025, 9, 15), (2025, 9, 23), (2025, 10, 13), (2025, 11, 3), (2025, 11, 24),
+        (2026, 1, 12), (2026, 2, 11), (2026, 2, 23), (2026, 3, 20), (2026, 4, 29), (2026, 5, 4), (2026, 5, 5),
+        (2026, 5, 6), (2026, 7, 20), (2026, 8, 11), (2026, 9, 21), (2026, 9, 22), (2026, 9, 23), (2026, 10, 12),
+        (2026, 11, 3), (2026, 11, 23),
+    ];
+    let mut day = date(2025, 1, 1);
+    while day < date(2027, 1, 1) {
+        let weekday_holiday = is_tse_holiday(day)
+            && !matches!(day.weekday(), Weekday::Sat | Weekday::Sun)
+            && !matches!((day.month(), day.day()), (12, 31) | (1, 1..=3));
+        assert_eq!(weekday_holiday, published.contains(&(day.year(), day.month(), day.day())), "{}", day);
+        day = day.succ_opt().unwrap();
+    }
+
+    // Later years follow the same rules: Coming of Age Day, the substitute
+    // for a Sunday equinox, Respect for the Aged Day and the equinox
+    for holiday in [date(2027, 1, 11), date(2027, 3, 22), date(2027, 9, 20), date(2027, 9, 23)] {
+        assert_eq!(tse_holiday(holiday), Some(true), "{}", holiday);
+    }
+}
+
+#[test]
+fn holidays_are_unknown_outside_the_calendar_years() {
+    assert_eq!(TSE_CALENDAR_YEARS, 2022..=2099);
+    assert_eq!(tse_holiday(date(2022, 1, 4)), Some(false));
+    assert_eq!(tse_holiday(date(2099, 12, 30)), Some(false));
+    assert_eq!(tse_holiday(date(2021, 12, 28)), None);
+    assert_eq!(tse_holiday(date(2100, 3, 22)), None);
+    // Weekends and the year-end break are known every year
+    assert_eq!(tse_holiday(date(2100, 1, 2)), Some(true));
+    assert!(!is_tse_holiday(date(2100, 3, 22)));
+}
+
+#[test]
+fn ttl_follows_the_tse_for_domestic_symbols_only() {
+    let trading = utc("2025-10-16T01:00:00Z");
+    let closed = utc("2025-10-16T09:00:00Z");
+    let holiday = utc("2025-10-19T01:00:00Z");
+
+    assert_eq!(cache_ttl(&symbol("7203"), trading), TRADING_TTL_SECONDS);
+    assert_eq!(cache_ttl(&symbol("7203"), closed), CLOSED_TTL_SECONDS);
+    assert_eq!(cache_ttl(&symbol("998407.O"), holiday), HOLIDAY_TTL_SECONDS);
+    assert_eq!(cache_ttl(&symbol("0331418A"), holiday), HOLIDAY_TTL_SECONDS);
+
+    for code in ["USDJPY=X", "^DJI", "AAPL"] {
+        assert_eq!(cache_ttl(&symbol(code), holiday), TRADING_TTL_SECONDS, "{}", code);
+    }
+}
+
+#[test]
+fn spellings_share_a_key() {
+    assert_eq!(cache_key(&symbol("7203"), "quote"), "7203.T:quote");
+    assert_eq!(cache_key(&symbol("7203.t"), "quote"), cache_key(&symbol("7203.T"), "quote"));
+    assert_ne!(cache_key(&symbol("7203"), "quote"), cache_key(&symbol("7203"), "history?page=2"));
+}
+
+#[test]
+fn memory_cache_expires_entries() {
+    let stored_at = utc("2025-10-16T01:00:00Z");
+    let mut cache = MemoryCache::default();
+    cache.put("7203.T:quote".to_string(), CachedPage::new("<html>".to_string(), stored_at, 60));
+
+    let page = cache.get("7203.T:quote", utc("2025-10-16T01:00:45Z")).unwrap();
+    assert_eq!(page.age(utc("2025-10-16T01:00:45Z")), 45);
+    assert!(cache.get("7203.T:quote", utc("2025-10-16T01:01:00Z")).is_none());
+    assert!(cache.get("6758.T:quote", stored_at).is_none());
+
+    // Expired entries are dropped on the next put
+    cache.put("6758.T:quote".to_string(), CachedPage::new(String::new(), utc("2025-10-16T01:05:00Z"), 60));
+    assert_eq!(cache.len(), 1);
+}
+
+#[test]
+fn cached_page_round_trips_through_json() {
+    let page = CachedPage::new("body".to_string(), utc("2025-10-16T01:00:00Z"), 1800);
+    let json = serde_json::to_string(&page).unwrap();
+    assert_eq!(serde_json::from_str::<CachedPage>(&json).unwrap(), page);
+}
@@ -0,0 +1,62 @@
+use preloaded_state::{extract_chart, extract_preloaded_state};
+use rust_decimal::Decimal;
+use serde_json::json;
+
+const STOCK_PAGE: &str = include_str!("../../fixtures/5016.T.html");
+
+#[test]
+fn extracts_daily_series_from_sample_state() {
+    let state = extract_preloaded_state(STOCK_PAGE).unwrap();
+    let chart = extract_chart(&state).unwrap();
+
+    assert_eq!(chart.term.as_deref(), Some("6m"));
+    assert_eq!(chart.time_frame.as_deref(), Some("daily"));
+    assert_eq!(chart.indicators, vec!["shortMovingAverage", "middleMovingAverage", "longMovingAverage"]);
+    assert_eq!(chart.points.len(), 145);
+
+    let first = &chart.points[0];
+    assert_eq!(first.timestamp.to_rfc3339(), "2025-04-03T00:00:00+09:00");
+    assert_eq!(first.price, Some(Decimal::from(844)));
+    assert_eq!(first.volume, Some(Decimal::from(16246100)));
+    assert!(chart.points.windows(2).all(|w| w[0].timestamp < w[1].timestamp));
+}
+
+#[test]
+fn reads_intraday_prices() {
+    let state = json!({
+        "mainItemDetailChartSetting": {
+            "currentChartTermKey": "1d",
+            "timeSeriesData": { "histories": [
+                { "baseDatetime": "2025-11-05T09:05:00+09:00", "price": "2,012.5", "volume": 120000 },
+                { "baseDatetime": "2025-11-05T09:00:00+09:00", "price": "2,010", "volume": null },
+                { "baseDatetime": "", "price": "2,000" }
+            ] }
+        }
+    });
+    let chart = extract_chart(&state).unwrap();
+
+    assert_eq!(chart.term.as_deref(), Some("1d"));
+    assert_eq!(chart.points.len(), 2);
+    assert_eq!(chart.points[0].price, Some(Decimal::from(2010)));
+    assert_eq!(chart.points[0].volume, None);
+    assert_eq!(chart.points[1].price, Some(Decimal::new(20125, 1)));
+}
+
+#[test]
+fn points_with_close_and_intraday_price_keep_the_close() {
+    let state = json!({
+        "mainItemDetailChartSetting": {
+            "timeSeriesData": { "histories": [
+                { "baseDatetime": "2025-11-05T00:00:00+09:00", "closePrice": "2,018", "price": "2,012.5" },
+                { "datetime": "2025-11-04T00:00:00+09:00", "baseDatetime": "2025-11-04T00:00:00+09:00", "price": 1995 },
+                // A malformed point drops only itself
+                { "baseDatetime": ["2025-11-03T00:00:00+09:00"], "closePrice": "1,980" }
+            ] }
+        }
+    });
+    let chart = extract_chart(&state).unwrap();
+
+    assert_eq!(chart.points.len(), 2);
+    assert_eq!(chart.points[0].price, Some(Decimal::from(1995)));
+    assert_eq!(chart.points[1].price, Some(Decimal::from(2018)));
+}
@@ -0,0 +1,60 @@
+use chrono::{DateTime, Utc};
+use preloaded_state::{verify_sources, Mismatch, Symbol};
+
+const DJI_PAGE: &str = include_str!("../../fixtures/^DJI.html");
+const STOCK_PAGE: &str = include_str!("../../fixtures/5016.T.html");
+
+fn fetched_at() -> DateTime<Utc> {
+    "2025-10-16T06:30:00Z".parse().unwrap()
+}
+
+/// A page carrying both an embedded price board and the DOM price board.
+fn page(state_price: &str, dom_price: &str) -> String {
+    format!(
+        r#"<html><body>
+<h1>トヨタ自動車(株)の株価・株式情報</h1>
+<div class="_CommonPriceBoard__priceBlock_1qr9b"><span class="_StyledNumber__value_x1ukl">{dom_price}</span></div>
+<span class="_PriceChangeLabel__primary_hse06"><span class="_StyledNumber__value_x1ukl">+12.5</span></span>
+<span class="_PriceChangeLabel__secondary_F0jBd"><span class="_StyledNumber__value_x1ukl">+0.44</span></span>
+<script>window.__PRELOADED_STATE__ = {{"mainStocksPriceBoard":{{"priceBoard":{{"code":"7203","name":"トヨタ自動車(株)","price":"{state_price}","priceChange":"+12.50","priceChangeRate":"+0.44"}}}}}}</script>
+</body></html>"#
+    )
+}
+
+#[test]
+fn agreeing_sources_are_consistent() {
+    let symbol: Symbol = "7203.T".parse().unwrap();
+    let consistency = verify_sources("7203.T", &symbol, &page("2,845.5", "2,845.50"), &[], fetched_at());
+    assert!(consistency.compared);
+    assert!(consistency.consistent, "{:?}", consistency);
+    assert!(consistency.mismatches.is_empty());
+}
+
+#[test]
+fn drifted_price_is_reported() {
+    let symbol: Symbol = "7203.T".parse().unwrap();
+    let consistency = verify_sources("7203.T", &symbol, &page("2,845.5", "2,790"), &[], fetched_at());
+    assert!(consistency.compared);
+    assert!(!consistency.consistent);
+    assert_eq!(
+        consistency.mismatches,
+        vec![Mismatch { field: "price", json: Some("2,845.5".to_string()), dom: Some("2,790".to_string()) }]
+    );
+}
+
+#[test]
+fn unreadable_source_is_not_compared() {
+    // ^DJI pages have no embedded state
+    let symbol: Symbol = "^DJI".parse().unwrap();
+    let consistency = verify_sources("^DJI", &symbol, DJI_PAGE, &[], fetched_at());
+    assert!(!consistency.compared && !consistency.consistent);
+    assert!(consistency.json_error.is_some());
+    assert_eq!(consistency.dom_error, None);
+
+    // Stock pages as captured lack the DOM price board
+    let symbol: Symbol = "5016.T".parse().unwrap();
+    let consistency = verify_sources("5016.T", &symbol, STOCK_PAGE, &[], fetched_at());
+    assert!(!consistency.compared);
+    assert_eq!(consistency.json_error, None);
+    assert!(consistency.dom_error.is_some());
+}
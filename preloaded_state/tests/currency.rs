@@ -0,0 +1,83 @@
+use std::str::FromStr;
+
+use preloaded_state::{
+    extract_preloaded_state, extract_us_quote, extract_usd_jpy, process_json_data, Format, OutputOptions, PriceBoard, Quote,
+    Symbol,
+};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+const US_PAGE: &str = include_str!("../../fixtures/AAPL.html");
+const STOCK_PAGE: &str = include_str!("../../fixtures/5016.T.html");
+const USDJPY_PAGE: &str = include_str!("../../fixtures/USDJPY=X.html");
+
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).unwrap()
+}
+
+fn board_quote(state: &serde_json::Value) -> Quote {
+    Quote::from(PriceBoard::deserialize(&state["mainStocksPriceBoard"]["priceBoard"]).unwrap())
+}
+
+#[test]
+fn reads_usd_jpy_bid() {
+    let state = extract_preloaded_state(USDJPY_PAGE).unwrap();
+    assert_eq!(extract_usd_jpy(&state), Some(dec("151.254")));
+
+    let stock = extract_preloaded_state(STOCK_PAGE).unwrap();
+    assert_eq!(extract_usd_jpy(&stock), None);
+}
+
+#[test]
+fn us_ticker_is_priced_in_usd_and_converted() {
+    let state = extract_preloaded_state(US_PAGE).unwrap();
+    let symbol = Symbol::from_str("AAPL").unwrap();
+    let mut quote = extract_us_quote(&state, &symbol, &board_quote(&state)).unwrap();
+    assert_eq!(quote.currency, "USD");
+    assert_eq!(quote.usd_price, Some(dec("247.45")));
+    assert_eq!(quote.us_market.as_deref(), Some("NASDAQ"));
+    assert_eq!(quote.us_link.as_deref(), Some("https://finance.yahoo.co.jp/quote/AAPL/"));
+    assert_eq!(quote.jpy_price, None);
+
+    quote.convert(dec("151.254"));
+    assert_eq!(quote.usd_jpy, Some(dec("151.254")));
+    assert_eq!(quote.jpy_price, Some(dec("37427.80")));
+}
+
+#[test]
+fn us_ticker_typed_times_are_new_york_local() {
+    let state = extract_preloaded_state(US_PAGE).unwrap();
+    let symbol = Symbol::from_str("AAPL").unwrap();
+    let output = OutputOptions {
+        keys: Some(vec!["update_time".to_string()]),
+        format: Format::Typed,
+        // 02:30 EDT on the day the page shows as "10/16"
+        fetched_at: "2025-10-16T06:30:00Z".parse().unwrap(),
+        usd_jpy: None,
+    };
+    let fields = process_json_data("AAPL", &symbol, &state, &[], &output).unwrap();
+    assert_eq!(fields["update_time"], "2025-10-16T00:00:00-04:00");
+}
+
+#[test]
+fn dual_listed_stock_reports_its_us_listing() {
+    let state = extract_preloaded_state(STOCK_PAGE).unwrap();
+    let symbol = Symbol::from_str("5016.T").unwrap();
+    let mut quote = extract_us_quote(&state, &symbol, &board_quote(&state)).unwrap();
+    assert_eq!(quote.currency, "JPY");
+    assert_eq!(quote.us_market.as_deref(), Some("OTC"));
+    assert_eq!(quote.us_link.as_deref(), Some("https://finance.yahoo.co.jp/quote/JXAMY"));
+    // "---" while the listing has not traded
+    assert_eq!(quote.usd_price_raw.as_deref(), Some("---"));
+    assert_eq!(quote.usd_price, None);
+
+    quote.convert(dec("151.254"));
+    assert_eq!(quote.jpy_price, None);
+}
+
+#[test]
+fn stock_without_us_listing_has_no_us_quote() {
+    let state = extract_preloaded_state(include_str!("../../fixtures/9643.N.html")).unwrap();
+    let symbol = Symbol::from_str("9643.N").unwrap();
+    assert_eq!(extract_us_quote(&state, &symbol, &board_quote(&state)), None);
+}
@@ -0,0 +1,66 @@
+use preloaded_state::{parse_data_sources, validate_data_sources, Board, DataSource, RegistryError};
+use serde_json::json;
+
+/// The registry the worker bundles.
+const BUNDLED: &str = include_str!("../../workers/data_sources.toml");
+
+fn source(name: &str, path: &str, board: Board, mappings: &[(&str, &str)]) -> DataSource {
+    DataSource {
+        name: name.to_string(),
+        path: path.to_string(),
+        board,
+        mappings: mappings.iter().map(|&(k, v)| (k.to_string(), v.to_string())).collect(),
+    }
+}
+
+#[test]
+fn bundled_registry_is_valid() {
+    let sources = parse_data_sources(BUNDLED).unwrap();
+    assert_eq!(sources[0], source("stock_price_board", "mainStocksPriceBoard.priceBoard", Board::Stock, &[]));
+}
+
+#[test]
+fn toml_and_json_documents_read_alike() {
+    let from_toml = parse_data_sources(BUNDLED).unwrap();
+    let json = json!({ "source": from_toml }).to_string();
+    assert_eq!(parse_data_sources(&json).unwrap(), from_toml);
+}
+
+#[test]
+fn malformed_paths_are_rejected() {
+    let problems = validate_data_sources(&[
+        source("empty_segment", "mainStocksPriceBoard..priceBoard", Board::Stock, &[]),
+        source("trailing_dot", "mainStocksPriceBoard.", Board::Stock, &[]),
+        source("spaced", "main Stocks.priceBoard", Board::Stock, &[]),
+    ]);
+    assert_eq!(
+        problems,
+        vec![
+            "empty_segment: bad path 'mainStocksPriceBoard..priceBoard'",
+            "trailing_dot: bad path 'mainStocksPriceBoard.'",
+            "spaced: bad path 'main Stocks.priceBoard'",
+        ]
+    );
+}
+
+#[test]
+fn mapped_boards_need_a_code_mapping() {
+    let problems = validate_data_sources(&[
+        source("no_code", "board", Board::Mapped, &[("price", "currentPrice")]),
+        source("typed", "board", Board::Stock, &[("code", "code")]),
+    ]);
+    assert_eq!(
+        problems,
+        vec!["no_code: missing `code` mapping", "typed: mappings are only used by mapped boards"]
+    );
+}
+
+#[test]
+fn duplicate_names_and_empty_registries_are_rejected() {
+    let board = source("board", "mainStocksPriceBoard.priceBoard", Board::Stock, &[]);
+    assert_eq!(validate_data_sources(&[board.clone(), board]), vec!["board: duplicate name"]);
+
+    assert_eq!(parse_data_sources("source = []"), Err(RegistryError::Invalid(vec!["no sources defined".to_string()])));
+    assert!(matches!(parse_data_sources("[[source]]\nname = "), Err(RegistryError::Syntax(_))));
+    assert!(matches!(parse_data_sources("{\"source\": [{\"name\": 1}]}"), Err(RegistryError::Syntax(_))));
+}
@@ -0,0 +1,46 @@
+use std::str::FromStr;
+
+use preloaded_state::{extract_preloaded_state, extract_stock_detail};
+use rust_decimal::Decimal;
+
+const STOCK_PAGE: &str = include_str!("../../fixtures/5016.T.html");
+
+fn dec(s: &str) -> Option<Decimal> {
+    Some(Decimal::from_str(s).unwrap())
+}
+
+#[test]
+fn extracts_detail_from_sample_state() {
+    let state = extract_preloaded_state(STOCK_PAGE).unwrap();
+    let detail = extract_stock_detail(&state).unwrap();
+
+    assert_eq!(detail.previous_price.value, dec("2095"));
+    assert_eq!(detail.previous_price.as_of.as_deref(), Some("11/04"));
+    assert_eq!(detail.open_price.value, dec("2012.5"));
+    assert_eq!(detail.open_price.as_of.as_deref(), Some("09:03"));
+    assert_eq!(detail.high_price.value, dec("2045"));
+    assert_eq!(detail.low_price.value, dec("1884.5"));
+    assert_eq!(detail.low_price.as_of.as_deref(), Some("11:29"));
+    assert_eq!(detail.volume.value, dec("36936000"));
+    assert_eq!(detail.volume.raw.as_deref(), Some("36,936,000"));
+    assert_eq!(detail.trading_value.value, dec("72728240"));
+
+    assert_eq!(detail.price_limit.lower, dec("1595"));
+    assert_eq!(detail.price_limit.upper, dec("2595"));
+    assert_eq!(detail.price_limit.as_of.as_deref(), Some("11/05"));
+
+    assert_eq!(detail.per.value, dec("26.73"));
+    assert_eq!(detail.per.basis.as_deref(), Some("(連)"));
+    assert_eq!(detail.pbr.value, dec("3.08"));
+    assert_eq!(detail.eps.value, dec("75.49"));
+    assert_eq!(detail.eps.as_of.as_deref(), Some("2026/03"));
+    assert_eq!(detail.bps.value, dec("654.62"));
+    assert_eq!(detail.shares_issued.value, dec("928463102"));
+    assert_eq!(detail.share_unit.value, dec("100"));
+}
+
+#[test]
+fn pages_without_detail_return_none() {
+    let state = serde_json::json!({ "mainCurrencyPriceBoard": {} });
+    assert_eq!(extract_stock_detail(&state), None);
+}
@@ -0,0 +1,109 @@
+use std::str::FromStr;
+
+use preloaded_state::{extract_dividend, extract_forecast, extract_preloaded_state, Rate};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+
+const STOCK_PAGE: &str = include_str!("../../fixtures/5016.T.html");
+const INDEX_PAGE: &str = include_str!("../../fixtures/998407.O.html");
+
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).unwrap()
+}
+
+fn stock_state() -> Value {
+    extract_preloaded_state(STOCK_PAGE).unwrap()
+}
+
+#[test]
+fn dividend_falls_back_to_price_board() {
+    // The sample's dividend section only holds "---" placeholders.
+    let dividend = extract_dividend(&stock_state()).unwrap();
+
+    assert_eq!(dividend.dps, Some(dec("18")));
+    assert_eq!(dividend.dps_date.as_deref(), Some("2026-03-01"));
+    assert_eq!(dividend.dividend_yield, Some(Rate::from_percent(dec("0.89"))));
+    assert_eq!(dividend.dividend_yield_time.as_deref(), Some("2025-11-05T15:30:00+09:00"));
+    assert_eq!(dividend.payout_ratio, None);
+}
+
+#[test]
+fn dividend_prefers_latest_dividend() {
+    // The sample's placeholders, filled in
+    let mut state = stock_state();
+    let latest = state.pointer_mut("/mainStocksDividend/latestDividend").unwrap();
+    latest["lastDpsFormatted"] = json!("20.00");
+    latest["lastDpsDateFormatted"] = json!("26/03");
+    latest["lastShareDividendYieldFormatted"] = json!("0.95");
+    latest["lastPayoutRatioFormatted"] = json!("26.5");
+    let dividend = extract_dividend(&state).unwrap();
+
+    assert_eq!(dividend.dps, Some(dec("20.00")));
+    assert_eq!(dividend.dps_raw.as_deref(), Some("20.00"));
+    assert_eq!(dividend.dps_date.as_deref(), Some("26/03"));
+    assert_eq!(dividend.dividend_yield, Some(Rate::from_percent(dec("0.95"))));
+    // The time has no digits in the sample, so the board's is kept
+    assert_eq!(dividend.dividend_yield_time.as_deref(), Some("2025-11-05T15:30:00+09:00"));
+    assert_eq!(dividend.payout_ratio, Some(Rate::from_percent(dec("26.5"))));
+}
+
+#[test]
+fn no_dividend_or_forecast_on_index_pages() {
+    let state = extract_preloaded_state(INDEX_PAGE).unwrap();
+    assert_eq!(extract_dividend(&state), None);
+    assert_eq!(extract_forecast(&state), None);
+}
+
+#[test]
+fn forecast_falls_back_to_community_predictions() {
+    let forecast = extract_forecast(&stock_state()).unwrap();
+
+    assert_eq!(forecast.source, "/stockPredictions/thisWeekUserPredictionData");
+    assert_eq!(forecast.median_target, Some(dec("2156.7")));
+    assert_eq!(forecast.median_target_rate, Some(Rate::from_percent(dec("5"))));
+    assert_eq!(forecast.total_votes, Some(39));
+    assert_eq!(forecast.ratings.len(), 7);
+    assert_eq!(forecast.ratings[0].label, "+5%");
+    assert_eq!(forecast.ratings[0].votes, Some(30));
+    assert_eq!(forecast.ratings[0].price, Some(dec("2156.7")));
+    assert_eq!(forecast.ratings[3].label, "±0%");
+}
+
+#[test]
+fn forecast_prefers_analyst_section() {
+    // The sample's analyst section is an empty link; give it the community figures
+    let mut state = stock_state();
+    let community = state["stockPredictions"]["thisWeekUserPredictionData"].clone();
+    state["mainStocksForecast"] = community;
+    state["mainStocksForecast"]["medianForecastPrice"] = json!("2,400");
+    let forecast = extract_forecast(&state).unwrap();
+
+    assert_eq!(forecast.source, "/mainStocksForecast");
+    assert_eq!(forecast.median_target, Some(dec("2400")));
+    assert_eq!(forecast.ratings.len(), 7);
+}
+
+#[test]
+fn forecast_reads_the_news_block_of_the_sample_page() {
+    // The sample's block only holds an empty link; give it figures in place
+    let mut state = stock_state();
+    let block = state.pointer_mut("/mainStocksNews/stockForecast").unwrap();
+    block["medianForecastPrice"] = json!("2,300");
+    block["totalVotes"] = json!(4);
+    let forecast = extract_forecast(&state).unwrap();
+
+    assert_eq!(forecast.source, "/mainStocksNews/stockForecast");
+    assert_eq!(forecast.median_target, Some(dec("2300")));
+    assert_eq!(forecast.total_votes, Some(4));
+}
+
+#[test]
+fn a_malformed_bucket_drops_only_itself() {
+    let mut state = stock_state();
+    let breakdown = state.pointer_mut("/stockPredictions/thisWeekUserPredictionData/breakdown").unwrap();
+    breakdown[1]["votes"] = json!("six");
+    let forecast = extract_forecast(&state).unwrap();
+
+    let labels: Vec<_> = forecast.ratings.iter().map(|rating| rating.label.as_str()).collect();
+    assert_eq!(labels, ["+5%", "+1%", "±0%", "-1%", "-3%", "-5%"]);
+}
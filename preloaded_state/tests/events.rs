@@ -0,0 +1,84 @@
+use chrono::{DateTime, Utc};
+use preloaded_state::{events_to_atom, events_to_rss, extract_events, extract_preloaded_state, EventKind};
+use serde_json::{json, Value};
+
+const STOCK_PAGE: &str = include_str!("../../fixtures/5016.T.html");
+
+fn fetched_at() -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339("2025-11-05T16:00:00+09:00").unwrap().to_utc()
+}
+
+/// The 5016.T state with `articles` and disclosure `items` added to its empty lists.
+fn state_with(articles: Value, items: Value) -> Value {
+    let mut state = extract_preloaded_state(STOCK_PAGE).unwrap();
+    state["mainStocksNews"]["articles"] = articles;
+    state["mainStocksDisclosure"]["items"] = items;
+    state
+}
+
+#[test]
+fn earnings_events_from_sample_state() {
+    let state = extract_preloaded_state(STOCK_PAGE).unwrap();
+    let events = extract_events("5016.T", &state, fetched_at());
+
+    let kinds: Vec<_> = events.iter().map(|e| e.kind).collect();
+    assert_eq!(kinds, vec![EventKind::EarningsSummary, EventKind::EarningsSchedule]);
+
+    let summary = &events[0];
+    assert_eq!(summary.timestamp.unwrap().to_rfc3339(), "2025-08-05T15:30:00+09:00");
+    assert!(summary.title.ends_with('。'));
+    assert!(summary.detail.as_ref().unwrap().len() > summary.title.len());
+
+    let schedule = &events[1];
+    assert_eq!(schedule.title, "次回の決算発表日は2025年11月11日の予定です。");
+    assert_eq!(schedule.timestamp.unwrap().to_rfc3339(), "2025-11-11T00:00:00+09:00");
+}
+
+#[test]
+fn merges_news_and_disclosures_with_the_earnings_dates() {
+    let state = state_with(
+        json!([
+            { "title": "Later news", "link": "https://news.example/2", "disclosedTime": "2025-11-05T09:00:00+09:00" },
+            { "title": "Undated news" }
+        ]),
+        json!([
+            { "title": "決算短信", "link": "https://disclosure.example/1.pdf", "disclosedTime": "2025/11/04 15:30" },
+            { "title": "" },
+            // A malformed record drops only itself
+            { "title": ["Not a title"] }
+        ]),
+    );
+    let events = extract_events("5016.T", &state, fetched_at());
+
+    let titles: Vec<_> = events.iter().map(|e| e.title.as_str()).collect();
+    assert_eq!(titles.len(), 5);
+    assert_eq!(titles[1..], ["決算短信", "Later news", "次回の決算発表日は2025年11月11日の予定です。", "Undated news"]);
+    assert_eq!(events[1].kind, EventKind::Disclosure);
+    assert_eq!(events[1].link.as_deref(), Some("https://disclosure.example/1.pdf"));
+    assert_eq!(events[1].timestamp.unwrap().to_rfc3339(), "2025-11-04T15:30:00+09:00");
+    assert_eq!(events[2].kind, EventKind::News);
+    assert_eq!(events[2].detail, None);
+    assert_eq!(events[4].timestamp, None);
+}
+
+#[test]
+fn renders_feeds_newest_first_and_escaped() {
+    let state = state_with(
+        json!([{ "title": "A & B <merger>", "link": "https://news.example/?a=1&b=2", "disclosedTime": "2025-11-05T09:00:00+09:00" }]),
+        json!([]),
+    );
+    let events = extract_events("5016.T", &state, fetched_at());
+
+    let atom = events_to_atom("5016.T", &events, fetched_at());
+    assert!(atom.contains("<updated>2025-11-05T07:00:00Z</updated>"));
+    assert!(atom.contains("<author><name>Yahoo!ファイナンス</name></author>"));
+    assert!(atom.contains("<title>A &amp; B &lt;merger&gt;</title>"));
+    assert!(atom.contains("<link href=\"https://news.example/?a=1&amp;b=2\"/>"));
+    assert!(atom.find("次回の決算発表日").unwrap() < atom.find("A &amp; B").unwrap());
+    assert!(atom.find("A &amp; B").unwrap() < atom.find("2026年3月期第1四半期").unwrap());
+
+    let rss = events_to_rss("5016.T", &events, "https://finance.yahoo.co.jp/quote/5016.T/");
+    assert!(rss.contains("<pubDate>Wed, 5 Nov 2025 09:00:00 +0900</pubDate>"));
+    assert!(rss.contains("<category>news</category>"));
+    assert!(rss.contains("<category>earnings_summary</category>"));
+}
@@ -0,0 +1,76 @@
+use preloaded_state::{extract_preloaded_state, locate_preloaded_state, StateError};
+
+const DJI_PAGE: &str = include_str!("../../fixtures/^DJI.html");
+const STOCK_PAGE: &str = include_str!("../../fixtures/5016.T.html");
+// Hand-annotated dump of a 5016.T page: wrapped mid-token and marked with `*` and `###`.
+const ANNOTATED_SAMPLE: &str = include_str!("../../window.__PRELOADED_STATE__ = {commo.txt");
+
+#[test]
+fn dji_page_has_no_preloaded_state() {
+    assert!(matches!(extract_preloaded_state(DJI_PAGE), Err(StateError::NotFound)));
+}
+
+#[test]
+fn extracts_stock_page_state() {
+    let state = extract_preloaded_state(STOCK_PAGE).unwrap();
+    assert_eq!(state["mainStocksPriceBoard"]["priceBoard"]["code"], "5016");
+    assert_eq!(state["pageInfo"]["code"], "5016.T");
+}
+
+#[test]
+fn span_covers_exactly_the_object() {
+    let span = locate_preloaded_state(STOCK_PAGE).unwrap();
+    assert!(STOCK_PAGE[span.start..].starts_with("{\"commonPortfolioAddition\""));
+    assert!(STOCK_PAGE[span.end..].starts_with("</script>"));
+}
+
+#[test]
+fn annotated_sample_is_balanced_but_malformed() {
+    let span = locate_preloaded_state(ANNOTATED_SAMPLE).unwrap();
+    assert_eq!(span.start, ANNOTATED_SAMPLE.find('{').unwrap());
+    assert_eq!(span.end, ANNOTATED_SAMPLE.trim_end().len());
+
+    match extract_preloaded_state(ANNOTATED_SAMPLE) {
+        Err(StateError::Malformed { span: reported, .. }) => assert_eq!(reported, span),
+        other => panic!("expected Malformed, got {:?}", other),
+    }
+}
+
+#[test]
+fn ignores_script_close_tag_inside_strings() {
+    let html = r#"<script>window.__PRELOADED_STATE__ = {"summary":"see </script> and {braces}","n":1}</script>"#;
+    let state = extract_preloaded_state(html).unwrap();
+    assert_eq!(state["summary"], "see </script> and {braces}");
+    assert_eq!(state["n"], 1);
+}
+
+#[test]
+fn tracks_escaped_quotes() {
+    let html = r#"<script>window.__PRELOADED_STATE__ = {"title":"say \"}\" \\","ok":true};</script>"#;
+    let state = extract_preloaded_state(html).unwrap();
+    assert_eq!(state["title"], r#"say "}" \"#);
+    assert_eq!(state["ok"], true);
+}
+
+#[test]
+fn stops_before_following_statements() {
+    let html = r#"<script>window.__PRELOADED_STATE__={"a":{"b":[1,2]}};window.__CONFIG__={"c":3};</script>"#;
+    let span = locate_preloaded_state(html).unwrap();
+    assert_eq!(&html[span.start..span.end], r#"{"a":{"b":[1,2]}}"#);
+}
+
+#[test]
+fn skips_non_assignment_mentions() {
+    let html = r#"<script>if (window.__PRELOADED_STATE__ == null) {}</script><script>window.__PRELOADED_STATE__ = {"x":1}</script>"#;
+    assert_eq!(extract_preloaded_state(html).unwrap()["x"], 1);
+}
+
+#[test]
+fn reports_unterminated_object() {
+    let html = r#"<script>window.__PRELOADED_STATE__ = {"a":{"b":1}</script>"#;
+    let start = html.find('{').unwrap();
+    assert!(matches!(
+        locate_preloaded_state(html),
+        Err(StateError::Unterminated { start: s }) if s == start
+    ));
+}
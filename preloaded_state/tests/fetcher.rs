@@ -0,0 +1,88 @@
+use std::time::Instant;
+
+use futures::future::LocalBoxFuture;
+use preloaded_state::{
+    extract_preloaded_state, fixture_name, Clock, FetchScheduler, FixtureFetcher, PageFetcher, Recording,
+    RecordingFetcher, ReplayFetcher, SchedulerConfig, Symbol, UpstreamError,
+};
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../fixtures");
+const DJI_PAGE: &str = include_str!("../../fixtures/^DJI.html");
+
+struct TokioClock(Instant);
+
+impl Clock for TokioClock {
+    fn now_millis(&self) -> u64 {
+        self.0.elapsed().as_millis() as u64
+    }
+
+    fn sleep(&self, millis: u64) -> LocalBoxFuture<'static, ()> {
+        Box::pin(tokio::time::sleep(std::time::Duration::from_millis(millis)))
+    }
+}
+
+fn scheduler() -> FetchScheduler<TokioClock> {
+    let config = SchedulerConfig {
+        requests_per_second: 0.0,
+        max_jitter_millis: 0,
+        ..SchedulerConfig::default()
+    };
+    FetchScheduler::new(config, TokioClock(Instant::now()))
+}
+
+#[test]
+fn names_fixtures_after_the_quote_path() {
+    assert_eq!(fixture_name("https://finance.yahoo.co.jp/quote/5016.T/"), "5016.T.html");
+    assert_eq!(fixture_name("https://finance.yahoo.co.jp/quote/USDJPY=X"), "USDJPY=X.html");
+    assert_eq!(
+        fixture_name("https://finance.yahoo.co.jp/quote/7203.T/history?page=2"),
+        "7203.T_history_page=2.html"
+    );
+    assert_eq!(fixture_name("https://finance.yahoo.co.jp/quote/^DJI/chart?term=1y"), "^DJI_chart_term=1y.html");
+}
+
+#[tokio::test]
+async fn serves_saved_pages_by_symbol() {
+    let fetcher = FixtureFetcher::new(FIXTURES);
+    let scheduler = scheduler();
+    for code in ["5016.T", "9643.N", "8596.F", "9534.S", "1306", "8951", "0331418A", "AAPL", "USDJPY=X"] {
+        let symbol: Symbol = code.parse().unwrap();
+        let body = scheduler.fetch(&fetcher, &symbol.quote_url()).await.unwrap();
+        assert!(extract_preloaded_state(&body).is_ok(), "{}", code);
+    }
+
+    let missing = scheduler.fetch(&fetcher, "https://finance.yahoo.co.jp/quote/9999.T/").await;
+    assert_eq!(missing, Err(UpstreamError::HttpStatus(404)));
+}
+
+#[tokio::test]
+async fn replays_a_page_without_state() {
+    let url = "^DJI".parse::<Symbol>().unwrap().quote_url();
+    let mut recording = Recording::default();
+    recording.insert(url.clone(), DJI_PAGE);
+    let fetcher = ReplayFetcher::new(recording);
+
+    let body = scheduler().fetch(&fetcher, &url).await.unwrap();
+    let state = extract_preloaded_state(&body).map_err(UpstreamError::from);
+    // The DJI page is the DOM fallback case
+    assert_eq!(state.unwrap_err(), UpstreamError::StateMissing);
+    assert!(body.contains("ダウ"));
+}
+
+#[tokio::test]
+async fn records_pages_for_replay() {
+    let recorder = RecordingFetcher::new(FixtureFetcher::new(FIXTURES));
+    let url = "https://finance.yahoo.co.jp/quote/5016.T/";
+    let body = recorder.fetch(url).await.unwrap();
+    assert!(recorder.fetch("https://finance.yahoo.co.jp/quote/9999.T/").await.is_err());
+
+    let path = std::env::temp_dir().join(format!("preloaded_state_recording_{}.json", std::process::id()));
+    recorder.recording().save(&path).unwrap();
+    let recording = Recording::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // Failed fetches are not recorded
+    assert_eq!(recording.pages.len(), 1);
+    let replayed = ReplayFetcher::new(recording).fetch(url).await.unwrap();
+    assert_eq!(replayed, body);
+}
@@ -0,0 +1,107 @@
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+use preloaded_state::{extract_preloaded_state, extract_price_history, Paged, Paging};
+use rust_decimal::Decimal;
+use serde_json::json;
+
+const STOCK_PAGE: &str = include_str!("../../fixtures/5016.T.html");
+
+fn dec(s: &str) -> Option<Decimal> {
+    Some(Decimal::from_str(s).unwrap())
+}
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+fn history_state(page: u32, has_next: bool, dates: &[&str]) -> serde_json::Value {
+    let histories: Vec<_> = dates
+        .iter()
+        .map(|d| {
+            json!({
+                "baseDatetime": d,
+                "openPrice": "2,010",
+                "highPrice": "2,045",
+                "lowPrice": "1,884.5",
+                "closePrice": "1,950",
+                "volume": "36,936,000",
+                "adjustedClosePrice": "1,950"
+            })
+        })
+        .collect();
+    json!({
+        "mainStocksHistory": {
+            "history": {
+                "yearHighPrice": "2,595",
+                "yearHighPriceDate": "2025/10/07",
+                "yearLowPrice": "1,050",
+                "yearLowPriceDate": "25/04/07",
+                "histories": histories
+            },
+            "paging": { "page": page, "totalPage": 2, "totalSize": 4, "hasNext": has_next }
+        }
+    })
+}
+
+#[test]
+fn extracts_bars_oldest_first() {
+    let state = history_state(1, true, &["2025/11/05", "2025/11/04", "bad date"]);
+    let history = extract_price_history(&state).unwrap();
+
+    assert_eq!(history.year_high.price, dec("2595"));
+    assert_eq!(history.year_high.date, Some(date(2025, 10, 7)));
+    assert_eq!(history.year_low.date, Some(date(2025, 4, 7)));
+
+    let dates: Vec<_> = history.bars.iter().map(|bar| bar.date).collect();
+    assert_eq!(dates, vec![date(2025, 11, 4), date(2025, 11, 5)]);
+    assert_eq!(history.bars[0].low, dec("1884.5"));
+    assert_eq!(history.bars[0].volume, dec("36936000"));
+    assert_eq!(history.paging.next_page(), Some(2));
+}
+
+#[test]
+fn a_malformed_row_drops_only_itself() {
+    let mut state = history_state(1, false, &["2025/11/05", "2025/11/04"]);
+    state["mainStocksHistory"]["history"]["histories"][0]["volume"] = json!({ "value": "36,936,000" });
+    state["mainStocksHistory"]["history"]["histories"]
+        .as_array_mut()
+        .unwrap()
+        .push(json!({ "baseDatetime": ["2025/11/03"], "closePrice": "1,980" }));
+    let history = extract_price_history(&state).unwrap();
+
+    let dates: Vec<_> = history.bars.iter().map(|bar| bar.date).collect();
+    assert_eq!(dates, vec![date(2025, 11, 4), date(2025, 11, 5)]);
+    assert_eq!(history.bars[1].volume, None);
+    assert_eq!(history.year_high.price, dec("2595"));
+}
+
+#[test]
+fn merges_following_pages() {
+    let mut history = extract_price_history(&history_state(1, true, &["2025/11/05", "2025/11/04"])).unwrap();
+    let next = extract_price_history(&history_state(2, false, &["2025/11/04", "2025/11/03"])).unwrap();
+    history.merge(next);
+
+    let dates: Vec<_> = history.bars.iter().map(|bar| bar.date).collect();
+    assert_eq!(dates, vec![date(2025, 11, 3), date(2025, 11, 4), date(2025, 11, 5)]);
+    assert_eq!(history.paging.next_page(), None);
+}
+
+#[test]
+fn renders_csv() {
+    let history = extract_price_history(&history_state(1, false, &["2025/11/04"])).unwrap();
+    assert_eq!(
+        history.to_csv(),
+        "date,open,high,low,close,volume,adjusted_close\n2025-11-04,2010,2045,1884.5,1950,36936000,1950\n"
+    );
+}
+
+#[test]
+fn empty_history_section() {
+    let state = extract_preloaded_state(STOCK_PAGE).unwrap();
+    let history = extract_price_history(&state).unwrap();
+
+    assert!(history.bars.is_empty());
+    assert_eq!(history.year_high.price, None);
+    assert_eq!(history.paging, Paging::default());
+}
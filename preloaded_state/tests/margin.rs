@@ -0,0 +1,137 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use preloaded_state::{extract_margin_history, extract_preloaded_state, MarginHistory, Paged};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+
+const STOCK_PAGE: &str = include_str!("../../fixtures/5016.T.html");
+
+fn dec(s: &str) -> Option<Decimal> {
+    Some(Decimal::from_str(s).unwrap())
+}
+
+fn fetched_at() -> DateTime<Utc> {
+    "2025-10-28T06:30:00Z".parse().unwrap()
+}
+
+/// The 5016.T state with its empty margin table filled with `rows` (date, buy,
+/// sell), newest first, each row a copy of the page's own margin summary.
+fn margin_page(page: u32, has_next: bool, rows: &[(&str, &str, &str)]) -> Value {
+    let mut state = extract_preloaded_state(STOCK_PAGE).unwrap();
+    let summary = state["mainStocksDetail"]["marginTransactionInfo"].clone();
+    let histories: Vec<_> = rows
+        .iter()
+        .map(|(date, buy, sell)| {
+            let mut row = summary.clone();
+            row["marginTransactionInfoDate"] = json!(date);
+            row["marginTransactionBuy"] = json!(buy);
+            row["marginTransactionSell"] = json!(sell);
+            row
+        })
+        .collect();
+    let section = state.pointer_mut("/mainStocksMarginHistory").unwrap();
+    section["histories"] = json!(histories);
+    section["paging"] = json!({ "page": page, "totalPage": 2, "hasNext": has_next });
+    state
+}
+
+fn dates(history: &MarginHistory) -> Vec<String> {
+    history.entries.iter().map(|e| e.date.to_string()).collect()
+}
+
+#[test]
+fn the_real_page_has_an_empty_table() {
+    let state = extract_preloaded_state(STOCK_PAGE).unwrap();
+    let history = extract_margin_history(&state, fetched_at()).unwrap();
+
+    assert!(history.entries.is_empty());
+    assert_eq!(history.paging.next_page(), None);
+}
+
+#[test]
+fn ratio_and_weekly_changes_match_the_summary() {
+    // The summary's week: 10/24 against 10/17, which is 10/24 less the summary's changes
+    let state = margin_page(
+        1,
+        false,
+        &[("10/24", "18,288,400", "1,851,700"), ("10/17", "17,979,800", "1,823,100"), ("10/10", "18,000,000", "0")],
+    );
+    let summary = &state["mainStocksDetail"]["marginTransactionInfo"];
+    let history = extract_margin_history(&state, fetched_at()).unwrap();
+    assert_eq!(dates(&history), ["2025-10-10", "2025-10-17", "2025-10-24"]);
+
+    let oldest = &history.entries[0];
+    assert_eq!(oldest.margin_ratio, None);
+    assert_eq!(oldest.buy_change_week, None);
+
+    let latest = &history.entries[2];
+    let summary_value = |key: &str| dec(&summary[key].as_str().unwrap().replace(',', ""));
+    assert_eq!(latest.margin_ratio, summary_value("marginCreditMagnification"));
+    assert_eq!(latest.buy_change_week, summary_value("marginTransactionBuyChange"));
+    assert_eq!(latest.sell_change_week, summary_value("marginTransactionSellChange"));
+}
+
+#[test]
+fn merged_pages_recompute_changes() {
+    let mut history = extract_margin_history(&margin_page(1, true, &[("10/24", "18,288,400", "1,851,700")]), fetched_at()).unwrap();
+    assert_eq!(history.entries[0].buy_change_week, None);
+    assert_eq!(history.paging().next_page(), Some(2));
+
+    let next = extract_margin_history(&margin_page(2, false, &[("10/17", "17,979,800", "1,823,100")]), fetched_at()).unwrap();
+    history.merge(next);
+
+    assert_eq!(history.entries.len(), 2);
+    assert_eq!(history.entries[1].buy_change_week, dec("308600"));
+    assert_eq!(history.paging().next_page(), None);
+}
+
+#[test]
+fn year_less_dates_count_back_from_the_fetch() {
+    let fetched_at = "2026-01-13T06:30:00Z".parse().unwrap();
+    let state = margin_page(
+        1,
+        false,
+        &[("1/9", "18,288,400", "1,851,700"), ("12/26", "17,979,800", "1,823,100"), ("12/19", "17,800,000", "1,800,000")],
+    );
+    let history = extract_margin_history(&state, fetched_at).unwrap();
+
+    assert_eq!(dates(&history), ["2025-12-19", "2025-12-26", "2026-01-09"]);
+    assert_eq!(history.entries[2].buy_change_week, dec("308600"));
+    assert_eq!(history.entries[2].margin_ratio, dec("9.88"));
+}
+
+#[test]
+fn december_rows_of_a_later_page_merge_before_its_january_rows() {
+    let fetched_at = "2026-01-13T06:30:00Z".parse().unwrap();
+    let first = margin_page(1, true, &[("1/9", "18,288,400", "1,851,700"), ("1/17", "17,500,000", "1,750,000")]);
+    let mut history = extract_margin_history(&first, fetched_at).unwrap();
+    assert_eq!(dates(&history), ["2025-01-17", "2026-01-09"]);
+
+    // Read alone, 1/10 lands in 2026 and 12/27 in 2025
+    let second = margin_page(2, false, &[("1/10", "17,400,000", "1,740,000"), ("12/27", "17,000,000", "1,700,000")]);
+    let alone = extract_margin_history(&second, fetched_at).unwrap();
+    assert_eq!(dates(&alone), ["2025-12-27", "2026-01-10"]);
+
+    history.merge(alone);
+    assert_eq!(dates(&history), ["2024-12-27", "2025-01-10", "2025-01-17", "2026-01-09"]);
+    assert_eq!(history.entries[1].buy_change_week, dec("400000"));
+    assert_eq!(history.entries[2].buy_change_week, dec("100000"));
+}
+
+#[test]
+fn later_pages_more_than_a_year_back_keep_their_year() {
+    let fetched_at = "2025-10-28T06:30:00Z".parse().unwrap();
+    let first = margin_page(1, true, &[("10/24", "18,288,400", "1,851,700"), ("1/10", "17,000,000", "1,700,000")]);
+    let mut history = extract_margin_history(&first, fetched_at).unwrap();
+    // Read alone, the second page's rows would land in 2025
+    let second = margin_page(2, false, &[("10/25", "16,000,000", "1,600,000"), ("10/18", "15,000,000", "1,500,000")]);
+    history.merge(extract_margin_history(&second, fetched_at).unwrap());
+
+    assert_eq!(dates(&history), ["2024-10-18", "2024-10-25", "2025-01-10", "2025-10-24"]);
+}
+
+#[test]
+fn pages_without_margin_history_return_none() {
+    assert_eq!(extract_margin_history(&json!({ "mainStocksHistory": {} }), fetched_at()), None);
+}
@@ -0,0 +1,57 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use preloaded_state::{parse_decimal, parse_rate, Quote, TradingCalendar, TypedQuote};
+use rust_decimal::Decimal;
+
+fn dec(s: &str) -> Decimal {
+    Decimal::from_str(s).unwrap()
+}
+
+#[test]
+fn parses_display_numbers() {
+    assert_eq!(parse_decimal("3,456.5"), Some(dec("3456.5")));
+    assert_eq!(parse_decimal("+77"), Some(dec("77")));
+    assert_eq!(parse_decimal("-1,884.5"), Some(dec("-1884.5")));
+    assert_eq!(parse_decimal("－１２．５"), Some(dec("-12.5")));
+    assert_eq!(parse_decimal("\u{2212}0.25"), Some(dec("-0.25")));
+    assert_eq!(parse_decimal("(+0.52%)"), Some(dec("0.52")));
+}
+
+#[test]
+fn placeholders_are_none() {
+    assert_eq!(parse_decimal("---"), None);
+    assert_eq!(parse_decimal(""), None);
+}
+
+#[test]
+fn rates_keep_percent_and_fraction() {
+    let rate = parse_rate("-3.68%").unwrap();
+    assert_eq!(rate.percent, dec("-3.68"));
+    assert_eq!(rate.fraction, dec("-0.0368"));
+}
+
+#[test]
+fn typed_quote_keeps_raw_strings() {
+    let quote = Quote {
+        code: "5016".to_string(),
+        price: Some("2,018".to_string()),
+        price_change: Some("-77".to_string()),
+        price_change_rate: Some("-3.68".to_string()),
+        dividend_yield: Some("0.89".to_string()),
+        update_time: Some("15:30".to_string()),
+        ..Quote::default()
+    };
+    let fetched_at: DateTime<Utc> = "2025-11-05T07:22:27Z".parse().unwrap();
+    let typed = TypedQuote::new(&quote, chrono_tz::Asia::Tokyo, TradingCalendar::Tse, fetched_at);
+    assert_eq!(typed.price, Some(dec("2018")));
+    assert_eq!(typed.price_raw.as_deref(), Some("2,018"));
+    assert_eq!(typed.price_change, Some(dec("-77")));
+    assert_eq!(typed.dividend_yield.unwrap().fraction, dec("0.0089"));
+
+    let json = serde_json::to_value(&typed).unwrap();
+    assert_eq!(json["price"], "2018");
+    assert_eq!(json["price_change_rate"]["percent"], "-3.68");
+    assert_eq!(json["update_time"], "2025-11-05T15:30:00+09:00");
+    assert_eq!(json["update_time_raw"], "15:30");
+}
@@ -0,0 +1,36 @@
+use preloaded_state::{extract_preloaded_state, find_object, find_object_paths, list_keys};
+use serde_json::json;
+
+const STOCK_PAGE: &str = include_str!("../../fixtures/5016.T.html");
+
+#[test]
+fn finds_the_price_board_of_the_stock_page() {
+    let state = extract_preloaded_state(STOCK_PAGE).unwrap();
+    let keys = vec!["code".to_string(), "price".to_string()];
+    let mut paths = Vec::new();
+    find_object_paths(&state, &keys, &mut Vec::new(), &mut paths);
+    assert_eq!(paths, [vec!["mainStocksPriceBoard", "priceBoard"], vec!["subRecentAccess", "registerItem"]]);
+
+    let board = find_object(&state, &paths[0]).unwrap();
+    assert_eq!(board["code"], "5016");
+    assert_eq!(find_object(&state, &["mainStocksPriceBoard", "missing"]), None);
+}
+
+#[test]
+fn lists_the_keys_of_the_stock_page() {
+    let state = extract_preloaded_state(STOCK_PAGE).unwrap();
+    let keys = list_keys(&state);
+    for key in ["pageInfo", "pageInfo.code", "mainStocksPriceBoard.priceBoard.code", "mainStocksPriceBoard.priceBoard.price"] {
+        assert!(keys.iter().any(|listed| listed == key), "{}", key);
+    }
+}
+
+#[test]
+fn paths_see_through_arrays() {
+    let state = json!({ "items": [{ "code": "7203", "price": "2,845" }, { "code": "6758" }] });
+    let keys = vec!["code".to_string(), "price".to_string()];
+    let mut paths = Vec::new();
+    find_object_paths(&state, &keys, &mut Vec::new(), &mut paths);
+    assert_eq!(paths, [vec!["items"]]);
+    assert_eq!(list_keys(&state), ["items", "items[0].code", "items[0].price", "items[1].code"]);
+}
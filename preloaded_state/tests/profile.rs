@@ -0,0 +1,68 @@
+use chrono::NaiveDate;
+use preloaded_state::{extract_company_profile, extract_preloaded_state};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+
+const STOCK_PAGE: &str = include_str!("../../fixtures/5016.T.html");
+const CURRENCY_PAGE: &str = include_str!("../../fixtures/USDJPY=X.html");
+
+/// The 5016.T state with `items` in its empty profile table.
+fn state_with(items: Value) -> Value {
+    let mut state = extract_preloaded_state(STOCK_PAGE).unwrap();
+    state["mainStocksProfile"]["items"] = items;
+    state
+}
+
+#[test]
+fn industry_comes_from_price_board() {
+    // The sample page's profile table is empty.
+    let state = extract_preloaded_state(STOCK_PAGE).unwrap();
+    let profile = extract_company_profile(&state).unwrap();
+
+    assert_eq!(profile.name.as_deref(), Some("ＪＸ金属(株)"));
+    assert_eq!(profile.industry.name.as_deref(), Some("非鉄金属"));
+    assert_eq!(
+        profile.industry.link.as_deref(),
+        Some("https://finance.yahoo.co.jp/search/qi/?ids=3500")
+    );
+    assert_eq!(profile.market.as_deref(), Some("東証PRM"));
+    assert!(profile.items.is_empty());
+}
+
+#[test]
+fn reads_profile_items() {
+    let state = state_with(json!([
+        { "title": "特色", "value": "半導体材料、情報通信材料が主力" },
+        { "title": "英文社名", "value": "JX Advanced Metals Corporation" },
+        { "title": "設立年月日", "value": "2002年9月27日" },
+        { "title": "上場年月日", "value": "2025年3月19日" },
+        { "title": "決算", "value": "3月末日" },
+        { "title": "単元株数", "value": "100株" },
+        { "title": "従業員数（連結）", "value": "8,971人" },
+        { "title": "従業員数 (単独)", "value": "2,383人" },
+        { "title": "平均年齢", "value": "43.2歳" },
+        { "title": "最寄り駅", "value": "" },
+        // A malformed row drops only itself
+        { "title": ["平均年収"], "value": "1,000万円" }
+    ]));
+    let profile = extract_company_profile(&state).unwrap();
+
+    assert_eq!(profile.name.as_deref(), Some("ＪＸ金属(株)"));
+    assert_eq!(profile.description.as_deref(), Some("半導体材料、情報通信材料が主力"));
+    assert_eq!(profile.english_name.as_deref(), Some("JX Advanced Metals Corporation"));
+    assert_eq!(profile.founded, NaiveDate::from_ymd_opt(2002, 9, 27));
+    assert_eq!(profile.listing_date, NaiveDate::from_ymd_opt(2025, 3, 19));
+    assert_eq!(profile.fiscal_year_end.as_deref(), Some("3月末日"));
+    assert_eq!(profile.share_unit, Some(Decimal::from(100)));
+    assert_eq!(profile.employees_consolidated, Some(Decimal::from(8971)));
+    assert_eq!(profile.employees_non_consolidated, Some(Decimal::from(2383)));
+    assert_eq!(profile.average_age, Some(Decimal::new(432, 1)));
+    assert_eq!(profile.average_annual_income, None);
+    assert_eq!(profile.items.len(), 9);
+}
+
+#[test]
+fn no_profile_on_currency_pages() {
+    let state = extract_preloaded_state(CURRENCY_PAGE).unwrap();
+    assert_eq!(extract_company_profile(&state), None);
+}
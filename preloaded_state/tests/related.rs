@@ -0,0 +1,54 @@
+use std::str::FromStr;
+
+use preloaded_state::{extract_preloaded_state, extract_related};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+
+const STOCK_PAGE: &str = include_str!("../../fixtures/5016.T.html");
+
+fn stock_state() -> Value {
+    extract_preloaded_state(STOCK_PAGE).unwrap()
+}
+
+#[test]
+fn extracts_attention_rankings_from_sample_state() {
+    let related = extract_related(&stock_state()).unwrap();
+
+    assert!(related.related_items.is_empty());
+
+    // Ranks 1-4 are empty placeholders on the sample page.
+    let one_day = related.one_day.as_ref().unwrap();
+    assert_eq!(one_day.stocks.len(), 1);
+    let stock = &one_day.stocks[0];
+    assert_eq!(stock.rank, 5);
+    assert_eq!(stock.code, "7272.T");
+    assert_eq!(stock.name.as_deref(), Some("ヤマハ発動機(株)"));
+    assert_eq!(stock.market.as_deref(), Some("東証プライム"));
+    assert_eq!(stock.access_count, Some(Decimal::from(14516)));
+    assert_eq!(stock.access_count_ratio, Some(Decimal::from_str("1.9082424083081373").unwrap()));
+    assert_eq!(stock.attention_level, Some(86));
+    assert_eq!(one_day.updated.unwrap().to_rfc3339(), "2025-11-05T16:07:02+09:00");
+
+    assert_eq!(related.codes(), vec!["7272.T", "6526.T"]);
+}
+
+#[test]
+fn related_items_come_first_without_duplicates() {
+    // The sample's ranked stocks as related items, around an empty slot and a malformed one
+    let mut state = stock_state();
+    let ranked = state["stockAttentionRanking"]["oneDay"]["data"]["attentionRankings"][4].clone();
+    let mut malformed = ranked.clone();
+    malformed["displayName"] = json!(["ヤマハ発動機(株)"]);
+    state["mainRelatedItem"]["relatedItems"] = json!([
+        { "stockCode": "5711.T", "displayName": "三菱マテリアル(株)", "marketName": "東証プライム" },
+        ranked,
+        { "stockCode": "" },
+        malformed
+    ]);
+    let related = extract_related(&state).unwrap();
+
+    assert_eq!(related.related_items.len(), 2);
+    assert_eq!(related.related_items[1].name.as_deref(), Some("ヤマハ発動機(株)"));
+    assert_eq!(related.related_items[1].market.as_deref(), Some("東証プライム"));
+    assert_eq!(related.codes(), vec!["5711.T", "7272.T", "6526.T"]);
+}
@@ -0,0 +1,255 @@
+use std::cell::{Cell, RefCell};
+use std::net::SocketAddr;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::future::{self, LocalBoxFuture};
+use preloaded_state::{url_host, Clock, FetchScheduler, SchedulerConfig, UpstreamError};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Real time, for runs against the mock server.
+struct TokioClock(Instant);
+
+impl Clock for TokioClock {
+    fn now_millis(&self) -> u64 {
+        self.0.elapsed().as_millis() as u64
+    }
+
+    fn sleep(&self, millis: u64) -> LocalBoxFuture<'static, ()> {
+        Box::pin(tokio::time::sleep(Duration::from_millis(millis)))
+    }
+}
+
+/// Frozen time that records the sleeps it is asked for.
+#[derive(Clone, Default)]
+struct FakeClock(Rc<RefCell<Vec<u64>>>);
+
+impl Clock for FakeClock {
+    fn now_millis(&self) -> u64 {
+        0
+    }
+
+    fn sleep(&self, millis: u64) -> LocalBoxFuture<'static, ()> {
+        self.0.borrow_mut().push(millis);
+        Box::pin(future::ready(()))
+    }
+}
+
+#[derive(Default)]
+struct ServerLog {
+    in_flight: AtomicUsize,
+    max_in_flight: AtomicUsize,
+    arrivals: Mutex<Vec<Instant>>,
+}
+
+/// Serves every request after `delay`, echoing its path.
+async fn mock_server(delay: Duration) -> (SocketAddr, Arc<ServerLog>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let log = Arc::new(ServerLog::default());
+    let server_log = log.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let log = server_log.clone();
+            tokio::spawn(async move {
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    if n == 0 {
+                        return;
+                    }
+                    request.extend_from_slice(&buf[..n]);
+                }
+                log.arrivals.lock().unwrap().push(Instant::now());
+                let now = log.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                log.max_in_flight.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(delay).await;
+                log.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+                let request = String::from_utf8_lossy(&request);
+                let path = request.split_whitespace().nth(1).unwrap_or("").to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    path.len(),
+                    path
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            });
+        }
+    });
+    (addr, log)
+}
+
+async fn fetch_all(scheduler: &FetchScheduler<TokioClock>, addr: SocketAddr, count: usize) -> Vec<String> {
+    let client = reqwest::Client::new();
+    let jobs = (0..count).map(|i| {
+        let client = client.clone();
+        async move {
+            let url = format!("http://{}/quote/{}", addr, i);
+            scheduler.throttle(url_host(&url)).await;
+            client.get(&url).send().await.unwrap().text().await.unwrap()
+        }
+    });
+    scheduler.run(jobs).await
+}
+
+#[tokio::test]
+async fn caps_requests_in_flight_and_keeps_order() {
+    let (addr, log) = mock_server(Duration::from_millis(80)).await;
+    let config = SchedulerConfig {
+        max_concurrency: 3,
+        requests_per_second: 0.0,
+        burst: 1,
+        max_jitter_millis: 0,
+        ..SchedulerConfig::default()
+    };
+    let scheduler = FetchScheduler::new(config, TokioClock(Instant::now()));
+
+    let bodies = fetch_all(&scheduler, addr, 9).await;
+    let expected: Vec<String> = (0..9).map(|i| format!("/quote/{}", i)).collect();
+    assert_eq!(bodies, expected);
+    assert_eq!(log.max_in_flight.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn spaces_requests_to_one_host() {
+    let (addr, log) = mock_server(Duration::ZERO).await;
+    let config = SchedulerConfig {
+        max_concurrency: 10,
+        requests_per_second: 20.0,
+        burst: 2,
+        max_jitter_millis: 0,
+        ..SchedulerConfig::default()
+    };
+    let scheduler = FetchScheduler::new(config, TokioClock(Instant::now()));
+
+    fetch_all(&scheduler, addr, 6).await;
+    let arrivals = log.arrivals.lock().unwrap();
+    let span = *arrivals.iter().max().unwrap() - *arrivals.iter().min().unwrap();
+    // Two from the burst, then one every 50 ms
+    assert!(span >= Duration::from_millis(190), "span {:?}", span);
+}
+
+#[tokio::test]
+async fn token_bucket_waits_grow_after_the_burst() {
+    let clock = FakeClock::default();
+    let config = SchedulerConfig {
+        max_concurrency: 1,
+        requests_per_second: 10.0,
+        burst: 2,
+        max_jitter_millis: 0,
+        ..SchedulerConfig::default()
+    };
+    let scheduler = FetchScheduler::new(config, clock.clone());
+    for _ in 0..4 {
+        scheduler.throttle("finance.yahoo.co.jp").await;
+    }
+    // Another host has its own bucket
+    scheduler.throttle("example.com").await;
+    assert_eq!(*clock.0.borrow(), vec![100, 200]);
+}
+
+#[tokio::test]
+async fn jitter_stays_within_bound() {
+    let clock = FakeClock::default();
+    let config = SchedulerConfig {
+        max_concurrency: 1,
+        requests_per_second: 0.0,
+        burst: 1,
+        max_jitter_millis: 50,
+        ..SchedulerConfig::default()
+    };
+    let scheduler = FetchScheduler::new(config, clock.clone());
+    for _ in 0..50 {
+        scheduler.throttle("finance.yahoo.co.jp").await;
+    }
+    let sleeps = clock.0.borrow();
+    assert!(sleeps.iter().all(|&ms| ms <= 50));
+    assert!(sleeps.windows(2).any(|pair| pair[0] != pair[1]));
+}
+
+/// A scheduler on `FakeClock` that only waits between retries.
+fn retrying_scheduler(clock: &FakeClock) -> FetchScheduler<FakeClock> {
+    let config = SchedulerConfig {
+        requests_per_second: 0.0,
+        max_jitter_millis: 0,
+        max_retries: 3,
+        retry_base_millis: 100,
+        ..SchedulerConfig::default()
+    };
+    FetchScheduler::new(config, clock.clone())
+}
+
+#[tokio::test]
+async fn retries_transient_failures_with_backoff() {
+    let clock = FakeClock::default();
+    let scheduler = retrying_scheduler(&clock);
+    let attempts = Cell::new(0);
+    let result = scheduler
+        .retry("finance.yahoo.co.jp", || {
+            attempts.set(attempts.get() + 1);
+            future::ready(match attempts.get() {
+                1 => Err(UpstreamError::HttpStatus(503)),
+                2 => Err(UpstreamError::Network("connection reset".to_string())),
+                _ => Ok("page"),
+            })
+        })
+        .await;
+    assert_eq!(result, Ok("page"));
+    assert_eq!(*clock.0.borrow(), vec![100, 200]);
+
+    // Retries are spent after max_retries, and the last error is returned
+    clock.0.borrow_mut().clear();
+    let result: Result<(), _> = scheduler
+        .retry("finance.yahoo.co.jp", || future::ready(Err(UpstreamError::HttpStatus(502))))
+        .await;
+    assert_eq!(result, Err(UpstreamError::HttpStatus(502)));
+    assert_eq!(*clock.0.borrow(), vec![100, 200, 400]);
+}
+
+#[tokio::test]
+async fn does_not_retry_permanent_failures() {
+    let clock = FakeClock::default();
+    let scheduler = retrying_scheduler(&clock);
+    let attempts = Cell::new(0);
+    let result: Result<(), _> = scheduler
+        .retry("finance.yahoo.co.jp", || {
+            attempts.set(attempts.get() + 1);
+            future::ready(Err(UpstreamError::Blocked))
+        })
+        .await;
+    assert_eq!(result, Err(UpstreamError::Blocked));
+    assert_eq!(attempts.get(), 1);
+    assert!(clock.0.borrow().is_empty());
+}
+
+#[tokio::test]
+async fn honours_retry_after_up_to_the_cap() {
+    let clock = FakeClock::default();
+    let scheduler = retrying_scheduler(&clock);
+    let attempts = Cell::new(0);
+    let result = scheduler
+        .retry("finance.yahoo.co.jp", || {
+            attempts.set(attempts.get() + 1);
+            future::ready(match attempts.get() {
+                1 => Err(UpstreamError::RateLimited { retry_after: Some(2) }),
+                _ => Err(UpstreamError::RateLimited { retry_after: Some(60) }),
+            })
+        })
+        .await;
+    // One retry after the requested 2 s; a minute is past MAX_RETRY_DELAY_MILLIS
+    assert_eq!(result, Err::<(), _>(UpstreamError::RateLimited { retry_after: Some(60) }));
+    assert_eq!(*clock.0.borrow(), vec![2000]);
+}
+
+#[test]
+fn host_of_url() {
+    assert_eq!(url_host("https://finance.yahoo.co.jp/quote/7203.T/"), "finance.yahoo.co.jp");
+    assert_eq!(url_host("http://127.0.0.1:8080/x?y=1"), "127.0.0.1:8080");
+    assert_eq!(url_host("https://user@example.com#top"), "example.com");
+}
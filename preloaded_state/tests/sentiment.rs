@@ -0,0 +1,45 @@
+use std::str::FromStr;
+
+use preloaded_state::{extract_preloaded_state, extract_sentiment};
+use rust_decimal::Decimal;
+use serde_json::json;
+
+const STOCK_PAGE: &str = include_str!("../../fixtures/5016.T.html");
+
+#[test]
+fn extracts_this_weeks_sentiment() {
+    let state = extract_preloaded_state(STOCK_PAGE).unwrap();
+    let sentiment = extract_sentiment(&state).unwrap();
+
+    assert_eq!(sentiment.plus_votes_percent, Some(Decimal::from_str("92").unwrap()));
+    assert_eq!(sentiment.minus_votes_percent, Some(Decimal::from_str("8").unwrap()));
+    assert_eq!(sentiment.total_votes, Some(39));
+    assert!(sentiment.has_enough_votes);
+    assert!(!sentiment.thin_sample);
+    assert_eq!(sentiment.plus_reasons.len(), 2);
+    assert_eq!(sentiment.minus_reasons, vec!["テクニカル（チャート・需給）"]);
+    assert_eq!(
+        sentiment.voting.deadline.unwrap().to_rfc3339(),
+        "2025-11-06T15:30:00+09:00"
+    );
+    assert_eq!(sentiment.voting.status.as_deref(), Some("open"));
+}
+
+#[test]
+fn flags_thin_samples() {
+    let state = json!({
+        "stockPredictions": {
+            "thisWeekUserPredictionData": { "hasEnoughVotes": false, "totalVotes": 3 }
+        }
+    });
+    let sentiment = extract_sentiment(&state).unwrap();
+
+    assert!(sentiment.thin_sample);
+    assert_eq!(sentiment.total_votes, Some(3));
+    assert_eq!(sentiment.plus_votes_percent, None);
+}
+
+#[test]
+fn no_sentiment_without_predictions() {
+    assert_eq!(extract_sentiment(&json!({ "mainCurrencyPriceBoard": {} })), None);
+}
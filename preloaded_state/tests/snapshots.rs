@@ -0,0 +1,162 @@
+//! Golden-file tests over the `fixtures/` corpus. After an intended change of
+//! output, refresh `fixtures/snapshots/` with `UPDATE_SNAPSHOTS=1 cargo test --workspace`.
+
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use futures::future::{self, LocalBoxFuture};
+use preloaded_state::{
+    discover_symbol, extract_chart, extract_company_profile, extract_dividend, extract_events, extract_forecast, extract_margin_history,
+    extract_preloaded_state, extract_price_history, extract_related, extract_sentiment, extract_stock_detail, fixture_name,
+    parse_data_sources, process_dom_data, process_json_data, Clock, DataSource, FetchScheduler, FixtureFetcher, Format,
+    OutputOptions, SchedulerConfig, StateError, Symbol, UpstreamError,
+};
+use serde_json::{json, Map, Value};
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../fixtures");
+/// The registry the worker bundles.
+const DATA_SOURCES: &str = include_str!("../../workers/data_sources.toml");
+
+/// Every page of the corpus: stocks on each market, an ETF, a REIT, a fund, a
+/// US ticker, both currency pages, a domestic index, a delisted stock and an
+/// index page without `__PRELOADED_STATE__`.
+const CORPUS: &[&str] = &[
+    "5016.T", "9643.N", "8596.F", "9534.S", "1306", "8951", "0331418A", "AAPL", "USDJPY=X", "USDJPY=FX", "998407.O",
+    "8303.T", "^DJI",
+];
+
+/// With no rate limit and no jitter nothing waits.
+struct Immediate;
+
+impl Clock for Immediate {
+    fn now_millis(&self) -> u64 {
+        0
+    }
+
+    fn sleep(&self, _millis: u64) -> LocalBoxFuture<'static, ()> {
+        Box::pin(future::ready(()))
+    }
+}
+
+fn fetched_at() -> DateTime<Utc> {
+    "2025-10-16T06:30:00Z".parse().unwrap()
+}
+
+/// The snapshot name for `symbol`: its fixture page's name, so `1306` and
+/// `1306.T` share one file name scheme.
+fn snapshot_name(symbol: &Symbol) -> String {
+    fixture_name(&symbol.quote_url()).trim_end_matches(".html").to_string()
+}
+
+fn page(symbol: &Symbol) -> String {
+    let path = FixtureFetcher::new(FIXTURES).path(&symbol.quote_url());
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+/// Compares `actual` with `fixtures/snapshots/<name>.json`, or rewrites the
+/// snapshot when `UPDATE_SNAPSHOTS` is set.
+fn assert_snapshot(name: &str, actual: &Value) {
+    let path = Path::new(FIXTURES).join("snapshots").join(format!("{}.json", name));
+    let actual = serde_json::to_string_pretty(actual).unwrap() + "\n";
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("No snapshot at {}; run with UPDATE_SNAPSHOTS=1", path.display()));
+    assert!(actual == expected, "{} differs from its snapshot:\n{}", path.display(), actual);
+}
+
+/// The worker's quote output for `code`: from the embedded state, or from
+/// the DOM when the page has none.
+fn quote(code: &str, data_sources: &[DataSource], format: Format) -> Value {
+    let symbol: Symbol = code.parse().unwrap();
+    let body = page(&symbol);
+    let output = OutputOptions {
+        keys: None,
+        format,
+        fetched_at: fetched_at(),
+        usd_jpy: Some("151.254".parse().unwrap()),
+    };
+    rendered(match extract_preloaded_state(&body) {
+        Ok(state) => process_json_data(code, &symbol, &state, data_sources, &output),
+        Err(StateError::NotFound) => process_dom_data(code, &body, &output),
+        Err(e) => Err(e.into()),
+    })
+}
+
+/// The output fields, or the error as the worker reports it.
+fn rendered(result: Result<Map<String, Value>, UpstreamError>) -> Value {
+    match result {
+        Ok(fields) => Value::Object(fields),
+        Err(e) => json!({ "error": e.to_string(), "error_kind": e.kind() }),
+    }
+}
+
+#[test]
+fn quote_output_of_every_page() {
+    let data_sources = parse_data_sources(DATA_SOURCES).unwrap();
+    for code in CORPUS {
+        let output = json!({
+            "raw": quote(code, &data_sources, Format::Raw),
+            "typed": quote(code, &data_sources, Format::Typed),
+        });
+        assert_snapshot(&format!("quote/{}", snapshot_name(&code.parse().unwrap())), &output);
+    }
+}
+
+#[test]
+fn dom_output_of_captured_pages() {
+    for code in ["^DJI", "5016.T"] {
+        let body = page(&code.parse().unwrap());
+        let options = |format| OutputOptions {
+            keys: None,
+            format,
+            fetched_at: fetched_at(),
+            usd_jpy: None,
+        };
+        let output = json!({
+            "raw": rendered(process_dom_data(code, &body, &options(Format::Raw))),
+            "typed": rendered(process_dom_data(code, &body, &options(Format::Typed))),
+        });
+        assert_snapshot(&format!("dom/{}", code), &output);
+    }
+}
+
+#[test]
+fn sections_of_a_stock_page() {
+    let state = extract_preloaded_state(&page(&"5016.T".parse().unwrap())).unwrap();
+    let output = json!({
+        "detail": extract_stock_detail(&state),
+        "dividend": extract_dividend(&state),
+        "forecast": extract_forecast(&state),
+        "sentiment": extract_sentiment(&state),
+        "profile": extract_company_profile(&state),
+        "related": extract_related(&state),
+        "chart": extract_chart(&state),
+        "events": extract_events("5016.T", &state, fetched_at()),
+        "history": extract_price_history(&state),
+        "margin": extract_margin_history(&state, fetched_at()),
+    });
+    assert_snapshot("sections/5016.T", &output);
+}
+
+#[tokio::test]
+async fn selector_discovery_of_every_page() {
+    let config = SchedulerConfig {
+        requests_per_second: 0.0,
+        max_jitter_millis: 0,
+        ..SchedulerConfig::default()
+    };
+    let scheduler = FetchScheduler::new(config, Immediate);
+    let fetcher = FixtureFetcher::new(FIXTURES);
+    for code in CORPUS {
+        let symbol: Symbol = code.parse().unwrap();
+        let output = match discover_symbol(&scheduler, &fetcher, code, &symbol).await {
+            Ok(discovered) => serde_json::to_value(discovered).unwrap(),
+            Err(e) => json!({ "error": e.to_string(), "error_kind": e.kind() }),
+        };
+        assert_snapshot(&format!("discovery/{}", snapshot_name(&symbol)), &output);
+    }
+}
@@ -0,0 +1,89 @@
+use chrono_tz::{America::New_York, Asia::Tokyo};
+use preloaded_state::{distinct_symbols, Market, Symbol, SymbolError};
+
+fn parse(code: &str) -> Symbol {
+    code.parse().unwrap()
+}
+
+#[test]
+fn parses_each_kind() {
+    let tokyo = |code: &str| Symbol::JpStock { code: code.to_string(), market: Market::Tokyo };
+    assert_eq!(parse("7203"), tokyo("7203"));
+    assert_eq!(parse("7203.T"), tokyo("7203"));
+    assert_eq!(parse(" 7203.t "), tokyo("7203"));
+    assert_eq!(parse("^dji"), Symbol::Index("^DJI".to_string()));
+    assert_eq!(parse("998407.O"), Symbol::Index("998407.O".to_string()));
+    assert_eq!(parse("USDJPY=X"), Symbol::CurrencyX("USDJPY".to_string()));
+    assert_eq!(parse("usdjpy=fx"), Symbol::CurrencyFx("USDJPY".to_string()));
+    assert_eq!(parse("0331418A"), Symbol::Fund("0331418A".to_string()));
+    assert_eq!(parse("aapl"), Symbol::UsStock("AAPL".to_string()));
+}
+
+#[test]
+fn parses_regional_markets_and_new_style_codes() {
+    let stock = |code: &str, market| Symbol::JpStock { code: code.to_string(), market };
+    assert_eq!(parse("9643.N"), stock("9643", Market::Nagoya));
+    assert_eq!(parse("8596.f"), stock("8596", Market::Fukuoka));
+    assert_eq!(parse("9534.S"), stock("9534", Market::Sapporo));
+    assert_eq!(parse("1306"), stock("1306", Market::Tokyo));
+    assert_eq!(parse("130a"), stock("130A", Market::Tokyo));
+    assert_eq!(parse("9534.S").quote_url(), "https://finance.yahoo.co.jp/quote/9534.S/");
+    assert_eq!(parse("0331418A").quote_url(), "https://finance.yahoo.co.jp/quote/0331418A/");
+}
+
+#[test]
+fn canonical_form_and_url() {
+    assert_eq!(parse("7203").canonical(), "7203.T");
+    assert_eq!(parse("7203").quote_url(), "https://finance.yahoo.co.jp/quote/7203.T/");
+    assert_eq!(parse("^DJI").quote_url(), "https://finance.yahoo.co.jp/quote/^DJI/");
+    assert_eq!(parse("usdjpy=fx").to_string(), "USDJPY=FX");
+    assert_eq!(parse("998407.O").canonical(), "998407.O");
+}
+
+#[test]
+fn bare_index_codes_default_to_tokyo() {
+    assert_eq!(parse("998405"), Symbol::Index("998405.T".to_string()));
+    assert_eq!(parse("998405").quote_url(), "https://finance.yahoo.co.jp/quote/998405.T/");
+    assert!(parse("998405").matches_board_code("998405"));
+}
+
+#[test]
+fn matches_board_codes_with_or_without_suffix() {
+    let stock = parse("7203.T");
+    assert_eq!(stock.board_code(), "7203");
+    assert!(stock.matches_board_code("7203"));
+    assert!(stock.matches_board_code(" 7203.T"));
+    assert!(!stock.matches_board_code("7201"));
+
+    let currency = parse("USDJPY=X");
+    assert!(currency.matches_board_code("USDJPY"));
+    assert!(currency.matches_board_code("USDJPY=X"));
+    assert!(parse("998407.O").matches_board_code("998407"));
+}
+
+#[test]
+fn timezone_follows_the_market() {
+    assert_eq!(parse("7203").timezone(), Tokyo);
+    assert_eq!(parse("^DJI").timezone(), New_York);
+    assert_eq!(parse("AAPL").timezone(), New_York);
+}
+
+#[test]
+fn rejects_unknown_codes() {
+    assert_eq!("  ".parse::<Symbol>(), Err(SymbolError::Empty));
+    assert!(matches!(
+        "7203.X".parse::<Symbol>(),
+        Err(SymbolError::UnknownMarket { suffix, .. }) if suffix == "X"
+    ));
+    assert!(matches!("72030.T".parse::<Symbol>(), Err(SymbolError::Malformed { .. })));
+    assert!(matches!("A130.T".parse::<Symbol>(), Err(SymbolError::Malformed { .. })));
+    assert!(matches!("=X".parse::<Symbol>(), Err(SymbolError::Malformed { .. })));
+    assert!(matches!("TOOLONG".parse::<Symbol>(), Err(SymbolError::Malformed { .. })));
+}
+
+#[test]
+fn batch_spellings_collapse_to_one_symbol() {
+    let codes = ["7203.T", "7203", "^DJI", "7203.t", "bad code", "usdjpy=x", "USDJPY=X"];
+    let expected = vec![parse("7203"), parse("^DJI"), parse("USDJPY=X")];
+    assert_eq!(distinct_symbols(&codes), expected);
+}
@@ -0,0 +1,90 @@
+use chrono::{DateTime, Utc};
+use chrono_tz::{America::New_York, Asia::Tokyo};
+use preloaded_state::{exchange_timezone, parse_market_time, Symbol, TradingCalendar};
+
+fn utc(s: &str) -> DateTime<Utc> {
+    s.parse().unwrap()
+}
+
+fn parse(raw: &str, tz: chrono_tz::Tz, fetched_at: &str) -> String {
+    let calendar = if tz == Tokyo { TradingCalendar::Tse } else { TradingCalendar::Weekdays };
+    parse_market_time(raw, tz, calendar, utc(fetched_at)).unwrap().to_rfc3339()
+}
+
+fn parse_for(raw: &str, code: &str, fetched_at: &str) -> String {
+    let symbol: Symbol = code.parse().unwrap();
+    parse_market_time(raw, symbol.timezone(), symbol.calendar(), utc(fetched_at)).unwrap().to_rfc3339()
+}
+
+#[test]
+fn rfc3339_keeps_its_offset() {
+    assert_eq!(
+        parse("2025-10-16T15:30:00+09:00", New_York, "2025-10-16T12:00:00Z"),
+        "2025-10-16T15:30:00+09:00"
+    );
+}
+
+#[test]
+fn time_of_day_uses_fetch_date() {
+    // Thursday 16:22 JST
+    assert_eq!(parse("15:30", Tokyo, "2025-10-16T07:22:00Z"), "2025-10-16T15:30:00+09:00");
+}
+
+#[test]
+fn time_of_day_later_than_fetch_is_previous_weekday() {
+    // Monday 08:00 JST, so the 15:30 close was on Friday
+    assert_eq!(parse("15:30", Tokyo, "2025-10-19T23:00:00Z"), "2025-10-17T15:30:00+09:00");
+}
+
+#[test]
+fn time_of_day_skips_tse_holidays() {
+    // Monday 2025-11-03 (Culture Day) 12:00 JST: the last close was Friday's
+    assert_eq!(parse("15:30", Tokyo, "2025-11-03T03:00:00Z"), "2025-10-31T15:30:00+09:00");
+    // Tuesday 08:00 JST, before the open after the holiday weekend
+    assert_eq!(parse("15:30", Tokyo, "2025-11-03T23:00:00Z"), "2025-10-31T15:30:00+09:00");
+    // New York trades on Japanese holidays
+    assert_eq!(parse("16:00", New_York, "2025-11-03T22:00:00Z"), "2025-11-03T16:00:00-05:00");
+}
+
+#[test]
+fn currencies_trade_through_tse_holidays() {
+    // Monday 2025-11-03 (Culture Day) 12:00 JST
+    assert_eq!(parse_for("11:55", "USDJPY=X", "2025-11-03T03:00:00Z"), "2025-11-03T11:55:00+09:00");
+    assert_eq!(parse_for("11:55", "EURJPY=FX", "2025-11-03T03:00:00Z"), "2025-11-03T11:55:00+09:00");
+    // Domestic stocks and indices keep the TSE calendar
+    assert_eq!(parse_for("15:30", "7203", "2025-11-03T03:00:00Z"), "2025-10-31T15:30:00+09:00");
+    assert_eq!(parse_for("15:15", "998407.O", "2025-11-03T03:00:00Z"), "2025-10-31T15:15:00+09:00");
+}
+
+#[test]
+fn us_index_times_are_new_york_local() {
+    // 16:00 EDT on the day and 16:00 EST after the DST change
+    assert_eq!(parse("16:00", New_York, "2025-10-16T21:30:00Z"), "2025-10-16T16:00:00-04:00");
+    assert_eq!(parse("16:00", New_York, "2025-11-05T21:30:00Z"), "2025-11-05T16:00:00-05:00");
+}
+
+#[test]
+fn month_day_infers_year() {
+    assert_eq!(parse("10/16", Tokyo, "2025-10-17T01:00:00Z"), "2025-10-16T00:00:00+09:00");
+    assert_eq!(parse("12/30", Tokyo, "2026-01-05T01:00:00Z"), "2025-12-30T00:00:00+09:00");
+}
+
+#[test]
+fn full_and_short_dates() {
+    assert_eq!(parse("2025/11/11", Tokyo, "2025-11-05T00:00:00Z"), "2025-11-11T00:00:00+09:00");
+    assert_eq!(parse("25/10/07", Tokyo, "2025-11-05T00:00:00Z"), "2025-10-07T00:00:00+09:00");
+    assert_eq!(parse("10/16 15:30", Tokyo, "2025-11-05T00:00:00Z"), "2025-10-16T15:30:00+09:00");
+}
+
+#[test]
+fn placeholders_do_not_parse() {
+    assert_eq!(parse_market_time("--:--", Tokyo, TradingCalendar::Tse, utc("2025-11-05T00:00:00Z")), None);
+    assert_eq!(parse_market_time("", Tokyo, TradingCalendar::Tse, utc("2025-11-05T00:00:00Z")), None);
+}
+
+#[test]
+fn exchange_timezones() {
+    assert_eq!(exchange_timezone("^DJI"), New_York);
+    assert_eq!(exchange_timezone("7203.T"), Tokyo);
+    assert_eq!(exchange_timezone("USDJPY=X"), Tokyo);
+}
@@ -0,0 +1,57 @@
+use preloaded_state::{extract_preloaded_state, UpstreamError};
+
+const DJI_PAGE: &str = include_str!("../../fixtures/^DJI.html");
+
+#[test]
+fn classifies_responses() {
+    assert_eq!(UpstreamError::from_response(200, None, "<html>__PRELOADED_STATE__</html>"), None);
+    assert_eq!(UpstreamError::from_response(404, None, ""), Some(UpstreamError::HttpStatus(404)));
+    assert_eq!(UpstreamError::from_response(503, None, ""), Some(UpstreamError::HttpStatus(503)));
+    assert_eq!(
+        UpstreamError::from_response(429, Some("30"), ""),
+        Some(UpstreamError::RateLimited { retry_after: Some(30) })
+    );
+    // An HTTP date is not a delay in seconds
+    assert_eq!(
+        UpstreamError::from_response(429, Some("Wed, 21 Oct 2026 07:28:00 GMT"), ""),
+        Some(UpstreamError::RateLimited { retry_after: None })
+    );
+    assert_eq!(UpstreamError::from_response(999, None, ""), Some(UpstreamError::Blocked));
+    assert_eq!(
+        UpstreamError::from_response(200, None, "<title>Please solve the CAPTCHA</title>"),
+        Some(UpstreamError::Blocked)
+    );
+}
+
+#[test]
+fn only_network_5xx_and_rate_limits_are_transient() {
+    let transient = [
+        UpstreamError::Network("connection reset".to_string()),
+        UpstreamError::HttpStatus(502),
+        UpstreamError::RateLimited { retry_after: None },
+    ];
+    assert!(transient.iter().all(UpstreamError::is_transient));
+
+    let permanent = [
+        UpstreamError::HttpStatus(404),
+        UpstreamError::Blocked,
+        UpstreamError::StateMissing,
+        UpstreamError::StateMalformed(String::new()),
+        UpstreamError::CodeMismatch(String::new()),
+        UpstreamError::DomSelectorsFailed(String::new()),
+    ];
+    assert!(permanent.iter().all(|error| !error.is_transient()));
+    assert_eq!(permanent[1].kind(), "blocked");
+    assert_eq!(permanent[5].kind(), "dom_selectors_failed");
+}
+
+#[test]
+fn state_errors_convert() {
+    let missing = extract_preloaded_state(DJI_PAGE).map_err(UpstreamError::from);
+    assert_eq!(missing.unwrap_err(), UpstreamError::StateMissing);
+
+    let malformed = extract_preloaded_state("<script>window.__PRELOADED_STATE__ = {\"a\":}</script>")
+        .map_err(UpstreamError::from)
+        .unwrap_err();
+    assert_eq!(malformed.kind(), "state_malformed");
+}
@@ -0,0 +1,44 @@
+//! The reqwest backend of `PageFetcher`.
+
+use futures::future::LocalBoxFuture;
+use preloaded_state::{PageFetcher, UpstreamError};
+
+/// Fetches pages over the network with one shared client, classifying error
+/// statuses and captcha pages.
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestFetcher {
+    client: reqwest::Client,
+}
+
+impl ReqwestFetcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl PageFetcher for ReqwestFetcher {
+    fn fetch<'a>(&'a self, url: &'a str) -> LocalBoxFuture<'a, Result<String, UpstreamError>> {
+        Box::pin(async move {
+            let resp = self
+                .client
+                .get(url)
+                .send()
+                .await
+                .map_err(|e| UpstreamError::Network(format!("Failed to fetch URL: {}", e)))?;
+            let status = resp.status().as_u16();
+            let retry_after = resp
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string);
+            let body = resp
+                .text()
+                .await
+                .map_err(|e| UpstreamError::Network(format!("Failed to read response text: {}", e)))?;
+            match UpstreamError::from_response(status, retry_after.as_deref(), &body) {
+                Some(error) => Err(error),
+                None => Ok(body),
+            }
+        })
+    }
+}
@@ -0,0 +1,86 @@
+//! Generation of serde struct definitions from objects found in
+//! `__PRELOADED_STATE__`, shared by the CLI and its snapshot tests.
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Recursively generates struct definitions and returns them as a Vec of strings.
+pub fn generate_structs(
+    name: &str,
+    value: &Value,
+    all_defs: &mut BTreeMap<String, String>,
+) -> Vec<String> {
+    if let Value::Object(map) = value {
+        let struct_name = to_pascal_case(name);
+        if all_defs.contains_key(&struct_name) {
+            return Vec::new(); // Already generated, return empty.
+        }
+
+        let mut fields = Vec::new();
+        let mut nested_defs_to_print = Vec::new();
+
+        for (k, v) in map {
+            let field_name = k;
+            let field_type = match v {
+                Value::Object(_) => {
+                    let nested_name = to_pascal_case(field_name);
+                    nested_defs_to_print.extend(generate_structs(&nested_name, v, all_defs));
+                    format!("Option<{}>", nested_name)
+                }
+                Value::Array(arr) => {
+                    if let Some(first) = arr.first() {
+                        match first {
+                            Value::Object(_) => {
+                                let singular_name = field_name.strip_suffix('s').unwrap_or(field_name);
+                                let nested_name = to_pascal_case(singular_name);
+                                nested_defs_to_print.extend(generate_structs(&nested_name, first, all_defs));
+                                format!("Option<Vec<{}>>", nested_name)
+                            }
+                            _ => "Option<Vec<String>>".to_string(),
+                        }
+                    } else {
+                        "Option<Vec<String>>".to_string()
+                    }
+                }
+                Value::String(_) => "Option<String>".to_string(),
+                Value::Number(n) => {
+                    if n.is_i64() { "Option<i64>".to_string() }
+                    else if n.is_u64() { "Option<u64>".to_string() }
+                    else { "Option<f64>".to_string() }
+                }
+                Value::Bool(_) => "Option<bool>".to_string(),
+                Value::Null => "Option<serde_json::Value>".to_string(),
+            };
+            fields.push(format!("    pub {}: {},", field_name, field_type));
+        }
+
+        let struct_def = format!(
+            "#[derive(Debug, serde::Deserialize)]\n\
+             pub struct {} {{\n{}\n}}",
+            struct_name,
+            fields.join("\n")
+        );
+
+        all_defs.insert(struct_name.clone(), struct_def.clone());
+        
+        let mut result = vec![struct_def];
+        result.extend(nested_defs_to_print);
+        result
+    } else {
+        Vec::new()
+    }
+}
+
+/// `mainStocksPriceBoard` -> `MainStocksPriceBoard`, `price_board` -> `PriceBoard`.
+pub fn to_pascal_case(s: &str) -> String {
+    s.split(|c: char| c == '_' || !c.is_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .map(|part| {
+            let mut c = part.chars();
+            match c.next() {
+                None => String::new(),
+                Some(f) => f.to_uppercase().collect::<String>() + c.as_str(),
+            }
+        })
+        .collect::<String>()
+}
@@ -0,0 +1,53 @@
+//! Snapshots of the CLI's struct generator; refreshed like the library's
+//! with `UPDATE_SNAPSHOTS=1 cargo test --workspace`.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use preloaded_state::{extract_preloaded_state, find_object, find_object_paths};
+use rust_extractor::{generate_structs, to_pascal_case};
+use serde_json::Value;
+
+const SNAPSHOTS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../fixtures/snapshots");
+const STOCK_PAGE: &str = include_str!("../../fixtures/5016.T.html");
+
+fn assert_snapshot(name: &str, actual: &str) {
+    let path = Path::new(SNAPSHOTS).join(name);
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("No snapshot at {}; run with UPDATE_SNAPSHOTS=1", path.display()));
+    assert!(actual == expected, "{} differs from its snapshot:\n{}", path.display(), actual);
+}
+
+/// The definitions the CLI prints for `--key code --key price`.
+#[test]
+fn structs_of_the_stock_price_board() {
+    let state = extract_preloaded_state(STOCK_PAGE).unwrap();
+    let keys = vec!["code".to_string(), "price".to_string()];
+    let mut paths = Vec::new();
+    find_object_paths(&state, &keys, &mut Vec::new(), &mut paths);
+    assert!(!paths.is_empty());
+
+    let mut all_defs = BTreeMap::new();
+    let mut output = String::new();
+    for path in &paths {
+        let object = find_object(&state, path).map(|map| Value::Object(map.clone())).unwrap();
+        output.push_str(&format!("// {}\n", path.join(".")));
+        for def in generate_structs(&to_pascal_case(path.last().unwrap()), &object, &mut all_defs) {
+            output.push_str(&def);
+            output.push_str("\n\n");
+        }
+    }
+    assert_snapshot("structs/5016.T.rs.txt", &output);
+}
+
+#[test]
+fn pascal_case() {
+    assert_eq!(to_pascal_case("mainStocksPriceBoard"), "MainStocksPriceBoard");
+    assert_eq!(to_pascal_case("price_board"), "PriceBoard");
+    assert_eq!(to_pascal_case("stock-predictions.items"), "StockPredictionsItems");
+}
@@ -0,0 +1,69 @@
+//! Cache of fetched Yahoo pages, keyed by canonical symbol and section.
+//!
+//! Pages are stored in the `QUOTE_CACHE` KV namespace when it is bound, so
+//! every isolate shares them; otherwise in memory, per isolate.
+
+use std::cell::RefCell;
+
+use chrono::{DateTime, Utc};
+use futures::future::LocalBoxFuture;
+use preloaded_state::{CachedPage, MemoryCache, PageStore};
+use worker::{console_error, kv::KvStore, Env};
+
+const QUOTE_CACHE_BINDING: &str = "QUOTE_CACHE";
+/// KV rejects expirations shorter than a minute.
+const KV_MIN_TTL_SECONDS: u64 = 60;
+
+thread_local! {
+    static MEMORY: RefCell<MemoryCache> = RefCell::new(MemoryCache::default());
+}
+
+pub enum PageCache {
+    Kv(KvStore),
+    Memory,
+}
+
+impl PageCache {
+    pub fn new(env: &Env) -> Self {
+        match env.kv(QUOTE_CACHE_BINDING) {
+            Ok(kv) => PageCache::Kv(kv),
+            Err(_) => PageCache::Memory,
+        }
+    }
+}
+
+impl PageStore for PageCache {
+    fn get<'a>(&'a self, key: &'a str, now: DateTime<Utc>) -> LocalBoxFuture<'a, Option<CachedPage>> {
+        Box::pin(async move {
+            match self {
+                PageCache::Kv(kv) => match kv.get(key).json::<CachedPage>().await {
+                    Ok(page) => page.filter(|page| page.is_fresh(now)),
+                    Err(e) => {
+                        console_error!("Failed to read {} from the page cache: {}", key, e);
+                        None
+                    }
+                },
+                PageCache::Memory => MEMORY.with(|memory| memory.borrow().get(key, now).cloned()),
+            }
+        })
+    }
+
+    /// Failures are logged, not returned.
+    fn put<'a>(&'a self, key: &'a str, page: CachedPage) -> LocalBoxFuture<'a, ()> {
+        Box::pin(async move {
+            match self {
+                PageCache::Kv(kv) => {
+                    let ttl = u64::from(page.ttl).max(KV_MIN_TTL_SECONDS);
+                    let stored = match kv.put(key, &page) {
+                        Ok(put) => put.expiration_ttl(ttl).execute().await,
+                        Err(e) => Err(e),
+                    };
+                    if let Err(e) = stored {
+                        console_error!("Failed to store {} in the page cache: {}", key, e);
+                    }
+                }
+                PageCache::Memory => MEMORY.with(|memory| memory.borrow_mut().put(key.to_string(), page)),
+            }
+        })
+    }
+}
@@ -0,0 +1,61 @@
+//! Registry of the locations in `__PRELOADED_STATE__` that hold quote data
+//! (`preloaded_state::DataSource`).
+//!
+//! The registry is bundled from `data_sources.toml` and can be replaced at
+//! runtime through the `CONFIG` KV namespace or the `DATA_SOURCES` variable.
+//! It is read and validated once per isolate, so a changed override takes
+//! effect as isolates are recycled.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use preloaded_state::{parse_data_sources, DataSource, RegistryError};
+use worker::{Env, Result};
+
+const BUNDLED_DATA_SOURCES: &str = include_str!("../data_sources.toml");
+const DATA_SOURCES_VAR: &str = "DATA_SOURCES";
+const CONFIG_KV_BINDING: &str = "CONFIG";
+const DATA_SOURCES_KV_KEY: &str = "data_sources";
+
+thread_local! {
+    static DATA_SOURCES: RefCell<Option<Rc<[DataSource]>>> = const { RefCell::new(None) };
+}
+
+/// The isolate's data sources, loaded on first use. A registry that fails to
+/// load is not kept, so the next request tries again.
+pub async fn load_data_sources(env: &Env) -> Result<Rc<[DataSource]>> {
+    if let Some(sources) = DATA_SOURCES.with(|cached| cached.borrow().clone()) {
+        return Ok(sources);
+    }
+    let sources: Rc<[DataSource]> = read_data_sources(env).await?.into();
+    DATA_SOURCES.with(|cached| *cached.borrow_mut() = Some(sources.clone()));
+    Ok(sources)
+}
+
+/// Reads and validates the data sources, preferring the KV override, then the
+/// `DATA_SOURCES` variable, then the bundled `data_sources.toml`.
+async fn read_data_sources(env: &Env) -> Result<Vec<DataSource>> {
+    if let Ok(kv) = env.kv(CONFIG_KV_BINDING) {
+        let text = kv
+            .get(DATA_SOURCES_KV_KEY)
+            .text()
+            .await
+            .map_err(|e| worker::Error::from(format!("Failed to read data sources from KV: {}", e)))?;
+        if let Some(text) = text {
+            return parse(&text, "KV CONFIG/data_sources");
+        }
+    }
+    if let Ok(var) = env.var(DATA_SOURCES_VAR) {
+        return parse(&var.to_string(), "variable DATA_SOURCES");
+    }
+    parse(BUNDLED_DATA_SOURCES, "data_sources.toml")
+}
+
+fn parse(text: &str, origin: &str) -> Result<Vec<DataSource>> {
+    parse_data_sources(text).map_err(|e| {
+        worker::Error::from(match e {
+            RegistryError::Syntax(_) => format!("Failed to parse data sources from {}: {}", origin, e),
+            RegistryError::Invalid(_) => format!("Invalid data sources from {}: {}", origin, e),
+        })
+    })
+}
@@ -0,0 +1,30 @@
+//! The Workers `Fetch` backend of `PageFetcher`.
+
+use futures::future::LocalBoxFuture;
+use preloaded_state::{PageFetcher, UpstreamError};
+use worker::{Fetch, Url};
+
+/// Fetches pages with the Workers runtime, classifying error statuses and
+/// captcha pages.
+pub struct WorkerFetcher;
+
+impl PageFetcher for WorkerFetcher {
+    fn fetch<'a>(&'a self, url: &'a str) -> LocalBoxFuture<'a, Result<String, UpstreamError>> {
+        Box::pin(async move {
+            let url = Url::parse(url).map_err(|e| UpstreamError::Network(format!("Invalid URL: {}", e)))?;
+            let mut resp = Fetch::Url(url)
+                .send()
+                .await
+                .map_err(|e| UpstreamError::Network(format!("Failed to fetch URL: {}", e)))?;
+            let body = resp
+                .text()
+                .await
+                .map_err(|e| UpstreamError::Network(format!("Failed to read response text: {}", e)))?;
+            let retry_after = resp.headers().get("Retry-After").ok().flatten();
+            match UpstreamError::from_response(resp.status_code(), retry_after.as_deref(), &body) {
+                Some(error) => Err(error),
+                None => Ok(body),
+            }
+        })
+    }
+}
@@ -1,334 +1,407 @@
-use futures::future::join_all;
-use regex::Regex;
-use scraper::{Html, Selector};
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use preloaded_state::{
+    cache_ttl, events_to_atom, events_to_rss, extract_company_profile, extract_dividend, extract_events, extract_forecast,
+    extract_margin_history, extract_preloaded_state, extract_price_history, extract_related, extract_sentiment, extract_stock_detail,
+    Format, MarginHistory, Related, Symbol, TRADING_TTL_SECONDS,
+};
 use serde::{Serialize};
-use serde_json::{Map, Value};
+use serde_json::Value;
 use worker::*;
 
+mod cache;
+mod data_source;
+mod fetcher;
+pub mod pipeline;
+mod schedule;
+use cache::PageCache;
+use data_source::load_data_sources;
+use fetcher::WorkerFetcher;
+use pipeline::{CodeResult, PageRequest, Upstream};
+use schedule::WorkerClock;
+
+/// How long `/profile` responses are cached; company profiles change a few times a year.
+const PROFILE_CACHE_SECONDS: u32 = 24 * 60 * 60;
+/// Most related codes resolved into quotes by `/related?resolve=true`.
+const MAX_RELATED_QUOTES: usize = 20;
+
 // Set up a panic hook to log errors to the console
 fn set_panic_hook() {
     console_error_panic_hook::set_once();
 }
 
-/// Defines a known location for financial data within the __PRELOADED_STATE__ JSON.
-struct DataSource {
-    path: &'static [&'static str],
-    mappings: std::collections::HashMap<&'static str, &'static str>,
-    strip_suffix: bool,
+/// `/related` response: the related tickers and, when resolved, their quotes.
+#[derive(Serialize, Debug)]
+struct RelatedResult {
+    #[serde(flatten)]
+    related: Related,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quotes: Option<Vec<CodeResult>>,
 }
 
-/// Represents the final JSON response for a single code.
-#[derive(Serialize, Debug)]
-struct CodeResult {
-    code: String,
-    data: Option<Map<String, Value>>,
-    error: Option<String>,
+/// The pipeline as the Workers runtime provides it: the page cache, `Fetch`
+/// and the isolate's scheduler.
+fn upstream(env: &Env) -> Upstream<WorkerClock> {
+    Upstream {
+        cache: Box::new(PageCache::new(env)),
+        fetcher: Box::new(WorkerFetcher),
+        scheduler: schedule::scheduler(),
+        now,
+    }
 }
 
 /// Main worker entry point.
 #[event(fetch)]
-pub async fn main(req: Request, _env: Env, _ctx: Context) -> Result<Response> {
+pub async fn main(req: Request, env: Env, _ctx: Context) -> Result<Response> {
     set_panic_hook();
+    schedule::configure(&env);
+
+    Router::new()
+        .get_async("/", |req, ctx| async move { handle_quotes(req, &ctx.env).await })
+        .get_async("/detail", |req, ctx| async move {
+            handle_section(req, &ctx.env, extract_stock_detail, "No stock detail (mainStocksDetail) in page.").await
+        })
+        .get_async("/dividend", |req, ctx| async move {
+            handle_section(req, &ctx.env, extract_dividend, "No dividend data in page.").await
+        })
+        .get_async("/forecast", |req, ctx| async move {
+            handle_section(req, &ctx.env, extract_forecast, "No forecast data in page.").await
+        })
+        .get_async("/profile", |req, ctx| async move { handle_profile(req, &ctx.env).await })
+        .get_async("/sentiment", |req, ctx| async move {
+            handle_section(req, &ctx.env, extract_sentiment, "No community sentiment (stockPredictions) in page.").await
+        })
+        .get_async("/chart", |req, ctx| async move { handle_chart(req, &ctx.env).await })
+        .get_async("/related", |req, ctx| async move { handle_related(req, &ctx.env).await })
+        .get_async("/events", |req, ctx| async move { handle_events(req, &ctx.env).await })
+        .get_async("/history", |req, ctx| async move { handle_history(req, &ctx.env).await })
+        .get_async("/margin", |req, ctx| async move { handle_margin(req, &ctx.env).await })
+        .run(req, env)
+        .await
+}
 
+/// `/?code=`: price board data for one or more codes. With `verify=true`
+/// each quote also carries a `consistency` block comparing its embedded state
+/// with the DOM.
+async fn handle_quotes(req: Request, env: &Env) -> Result<Response> {
     let url = req.url()?;
-    let query_params: std::collections::HashMap<String, String> = url.query_pairs().into_owned().collect();
+    let query_params: HashMap<String, String> = url.query_pairs().into_owned().collect();
 
-    let codes_str = match query_params.get("code") {
-        Some(c) => c,
-        None => return Response::error("Query parameter 'code' is required. e.g., ?code=7203.T,^DJI", 400),
+    let codes = match parse_codes(&query_params) {
+        Ok(codes) => codes,
+        Err(message) => return Response::error(message, 400),
     };
 
-    let codes: Vec<String> = codes_str
-        .split(',')
-        .filter(|s| !s.is_empty())
-        .map(|s| s.trim().to_string())
-        .collect();
+    let (keys, format) = match parse_output_params(&query_params) {
+        Ok(params) => params,
+        Err(message) => return Response::error(message, 400),
+    };
+    let verify = matches!(query_params.get("verify").map(String::as_str), Some("true" | "1"));
 
-    if codes.is_empty() {
-        return Response::error("Query parameter 'code' cannot be empty.", 400);
-    }
+    let data_sources = match load_data_sources(env).await {
+        Ok(sources) => sources,
+        Err(e) => return Response::error(e.to_string(), 500),
+    };
 
-    let keys: Option<Vec<String>> = query_params
-        .get("keys")
-        .map(|s| s.split(',').map(|k| k.trim().to_string()).collect());
+    let results = upstream(env).fetch_quotes(&codes, keys, format, verify, &data_sources).await;
 
-    let futures = codes
-        .iter()
-        .map(|code| fetch_single_code(code.clone(), keys.clone()));
-    let results = join_all(futures).await;
+    let all_ok = results.iter().all(|result| result.error.is_none());
+    json_response(&results, &cache_control(&codes, all_ok, oldest(&results)))
+}
+
+/// `/chart?code=&term=`: the embedded price series of a single code as
+/// `(timestamp, price, volume)` points. Without `term` the page's default
+/// term is returned.
+async fn handle_chart(req: Request, env: &Env) -> Result<Response> {
+    let url = req.url()?;
+    let query_params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+
+    let code = match parse_codes(&query_params) {
+        Ok(codes) if codes.len() == 1 => codes[0].clone(),
+        Ok(_) => return Response::error("Charts are available for one code at a time.", 400),
+        Err(message) => return Response::error(message, 400),
+    };
+    let term = query_params.get("term").filter(|t| !t.is_empty()).cloned();
+    if term.as_ref().is_some_and(|t| !t.chars().all(|c| c.is_ascii_alphanumeric())) {
+        return Response::error("Invalid term. e.g., term=1d, 1m, 6m, 1y", 400);
+    }
 
-    Response::from_json(&results)
+    let result = upstream(env).fetch_chart(code.clone(), term).await;
+    json_response(&result, &cache_control(&[code], result.error.is_none(), result.age))
 }
 
-/// Fetches and processes data for a single stock code.
-async fn fetch_single_code(code: String, keys: Option<Vec<String>>) -> CodeResult {
-    let url = if code.starts_with('^') || code.contains('=') || code.ends_with(".T") || code.ends_with(".O") {
-        format!("https://finance.yahoo.co.jp/quote/{}/", code)
-    } else {
-        format!("https://finance.yahoo.co.jp/quote/{}.T/", code)
+/// `/related?code=`: related tickers and attention rankings of a single code.
+/// With `resolve=true` each related code is also fetched like `/?code=`,
+/// honouring `keys`, `format` and `verify`.
+async fn handle_related(req: Request, env: &Env) -> Result<Response> {
+    let url = req.url()?;
+    let query_params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+
+    let code = match parse_codes(&query_params) {
+        Ok(codes) if codes.len() == 1 => codes[0].clone(),
+        Ok(_) => return Response::error("Related items are available for one code at a time.", 400),
+        Err(message) => return Response::error(message, 400),
+    };
+    let resolve = matches!(query_params.get("resolve").map(String::as_str), Some("true" | "1"));
+    let verify = matches!(query_params.get("verify").map(String::as_str), Some("true" | "1"));
+    let (keys, format) = match parse_output_params(&query_params) {
+        Ok(params) => params,
+        Err(message) => return Response::error(message, 400),
     };
 
-    let body = match Fetch::Url(url.parse().unwrap()).send().await {
-        Ok(mut resp) => match resp.text().await {
-            Ok(text) => text,
-            Err(e) => return CodeResult { code, data: None, error: Some(format!("Failed to read response text: {}", e)) },
-        },
-        Err(e) => return CodeResult { code, data: None, error: Some(format!("Failed to fetch URL: {}", e)) },
+    let upstream = upstream(env);
+    let related = upstream.fetch_section(code, extract_related, "No related items in page.").await;
+    let CodeResult { code, data: Some(related), error: None, age, .. } = related else {
+        return json_response(&related, "no-store");
     };
 
-    let re = Regex::new(r"(?s)window\.__PRELOADED_STATE__\s*=\s*(.*?)</script>").unwrap();
-
-    let result_data: Result<Map<String, Value>> = if let Some(caps) = re.captures(&body) {
-        if let Some(json_match) = caps.get(1) {
-            let mut json_str = json_match.as_str().trim();
-            if json_str.ends_with(';') {
-                json_str = &json_str[..json_str.len() - 1];
-            }
-
-            match serde_json::from_str(json_str) {
-                Ok(data) => process_json_data(&code, &data, keys.as_ref()),
-                Err(e) => Err(worker::Error::from(format!("Failed to parse JSON: {}", e))),
-            }
-        } else {
-            // JSON not found, fallback to DOM
-            process_dom_data(&code, &body, keys.as_ref())
-        }
+    let quotes = if resolve {
+        let data_sources = match load_data_sources(env).await {
+            Ok(sources) => sources,
+            Err(e) => return Response::error(e.to_string(), 500),
+        };
+        let codes: Vec<String> = related.codes().into_iter().take(MAX_RELATED_QUOTES).collect();
+        Some(upstream.fetch_quotes(&codes, keys, format, verify, &data_sources).await)
     } else {
-        // __PRELOADED_STATE__ script not found, fallback to DOM
-        process_dom_data(&code, &body, keys.as_ref())
+        None
     };
 
-    match result_data {
-        Ok(data) => CodeResult { code, data: Some(data), error: None },
-        Err(e) => CodeResult { code, data: None, error: Some(e.to_string()) },
-    }
+    let all_ok = quotes.iter().flatten().all(|quote| quote.error.is_none());
+    let oldest = quotes.as_deref().map_or(age, |quotes| age.max(oldest(quotes)));
+    let cache_control = cache_control(std::slice::from_ref(&code), all_ok, oldest);
+    let data = RelatedResult { related, quotes };
+    let result = CodeResult::new(code, Some(data), None).served(age);
+    json_response(&result, &cache_control)
 }
 
-/// Processes the __PRELOADED_STATE__ JSON data to find financial info.
-fn process_json_data(code: &str, data: &Value, keys: Option<&Vec<String>>) -> Result<Map<String, Value>> {
-    let data_sources = get_data_sources();
-
-    // 1. Try predefined paths
-    for source in &data_sources {
-        if let Some(target_obj) = find_object(data, source.path) {
-            if let Some(json_code_key) = source.mappings.get("code") {
-                 if let Some(found_code) = get_string_value(target_obj, json_code_key) {
-                    let code_to_compare = if source.strip_suffix {
-                        code.split('.').next().unwrap_or(code)
-                    } else {
-                        code
-                    };
-
-                    if found_code.trim() == code_to_compare {
-                        let mut results = Map::new();
-                        if let Some(keys_vec) = keys {
-                            for key in keys_vec {
-                                if let Some(json_key) = source.mappings.get(key.as_str()) {
-                                    if let Some(value) = target_obj.get(*json_key) {
-                                        // Convert numbers/booleans to strings for consistency, then re-wrap as Value
-                                        let str_val = value.to_string().trim_matches('"').to_string();
-                                        results.insert(key.clone(), Value::String(str_val));
-                                    }
-                                } else if key == "code" {
-                                    results.insert("code".to_string(), Value::String(code.to_string()));
-                                }
-                            }
-                        } else {
-                            // If keys is None, return the entire target_obj
-                            results = target_obj.clone();
-                            results.insert("code".to_string(), Value::String(code.to_string())); // Ensure code is present
-                        }
-                        results.insert("status".to_string(), Value::String("OK".to_string()));
-                        results.insert("source".to_string(), Value::String("json_predefined".to_string()));
-                        return Ok(results);
-                    }
-                }
-            }
-        }
+/// `/detail`, `/dividend`, `/forecast`, `/sentiment` (`?code=`): one typed
+/// section of the quote page for one or more stocks.
+async fn handle_section<T: Serialize>(
+    req: Request,
+    env: &Env,
+    extract: fn(&Value) -> Option<T>,
+    missing: &'static str,
+) -> Result<Response> {
+    let url = req.url()?;
+    let query_params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+
+    let codes = match parse_codes(&query_params) {
+        Ok(codes) => codes,
+        Err(message) => return Response::error(message, 400),
+    };
+
+    let results = upstream(env).fetch_sections(&codes, extract, missing).await;
+
+    let all_ok = results.iter().all(|result| result.error.is_none());
+    json_response(&results, &cache_control(&codes, all_ok, oldest(&results)))
+}
+
+/// `/profile?code=`: company profiles for one or more stocks. Complete
+/// responses are kept in the Workers cache for `PROFILE_CACHE_SECONDS`.
+async fn handle_profile(req: Request, env: &Env) -> Result<Response> {
+    let url = req.url()?;
+    let cache = Cache::default();
+    if let Ok(Some(cached)) = cache.get(url.as_str(), false).await {
+        return Ok(cached);
     }
 
-    // 2. Fallback to generic key search
-    let fallback_keys_to_find = vec!["code".to_string()];
-    let mut found_paths = Vec::new();
-    find_object_paths(data, &fallback_keys_to_find, &mut Vec::new(), &mut found_paths);
-
-    let fallback_mappings = HashMap::from([
-        ("name", "name"),
-        ("price", "price"),
-        ("price_change", "priceChange"),
-        ("price_change_rate", "priceChangeRate"),
-        ("update_time", "priceDateTime"),
-    ]);
-
-    for path in found_paths {
-        let mut target_obj = data;
-        for &key in &path {
-            target_obj = &target_obj[key];
-        }
-        if let Some(obj_map) = target_obj.as_object() {
-            if let Some(found_code) = get_string_value(obj_map, "code") {
-                let code_to_compare = code.split('.').next().unwrap_or(code);
-                if found_code.trim() == code_to_compare {
-                    let mut results = Map::new();
-                    if let Some(keys_vec) = keys {
-                        for key in keys_vec {
-                            if let Some(json_key) = fallback_mappings.get(key.as_str()) {
-                                if let Some(value) = obj_map.get(*json_key) {
-                                    let str_val = value.to_string().trim_matches('"').to_string();
-                                    results.insert(key.clone(), Value::String(str_val));
-                                }
-                            } else if key == "code" {
-                                results.insert("code".to_string(), Value::String(code.to_string()));
-                            }
-                        }
-                    } else {
-                        // If keys is None, return the entire target_obj
-                        results = obj_map.clone();
-                        results.insert("code".to_string(), Value::String(code.to_string())); // Ensure code is present
-                    }
-                    results.insert("status".to_string(), Value::String("OK".to_string()));
-                    results.insert("source".to_string(), Value::String("json_fallback".to_string()));
-                    return Ok(results);
-                }
-            }
+    let query_params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+    let codes = match parse_codes(&query_params) {
+        Ok(codes) => codes,
+        Err(message) => return Response::error(message, 400),
+    };
+
+    let results = upstream(env).fetch_sections(&codes, extract_company_profile, "No company profile in page.").await;
+
+    let mut response = Response::from_json(&results)?;
+    if results.iter().all(|result| result.error.is_none()) {
+        response
+            .headers_mut()
+            .set("Cache-Control", &format!("public, max-age={}", PROFILE_CACHE_SECONDS))?;
+        if let Err(e) = cache.put(url.as_str(), response.cloned()?).await {
+            console_error!("Failed to cache profile response: {}", e);
         }
     }
-
-    Err(worker::Error::from("Could not find matching data in JSON."))
+    Ok(response)
 }
 
-/// Processes the HTML body using CSS selectors as a fallback.
-fn process_dom_data(code: &str, body: &str, keys: Option<&Vec<String>>) -> Result<Map<String, Value>> {
-    let document = Html::parse_document(body);
-    let mut results = Map::new();
-
-    // Create a map of known keys to their selectors
-    let mut selector_map = std::collections::HashMap::new();
-    selector_map.insert("name", "h1");
-    selector_map.insert("price", "div[class*='_CommonPriceBoard__priceBlock'] span[class*='_StyledNumber__value']");
-    selector_map.insert("price_change", "span[class*='_PriceChangeLabel__primary'] span[class*='_StyledNumber__value']");
-    selector_map.insert("price_change_rate", "span[class*='_PriceChangeLabel__secondary'] span[class*='_StyledNumber__value']");
-    selector_map.insert("update_time", "li[class*='_CommonPriceBoard__time'] time, span[class*='_Time']");
-
-    let keys_to_process = if let Some(k) = keys {
-        k.clone()
-    } else {
-        // Default keys if not provided (for DOM, this means all known fields)
-        vec![
-            "code".to_string(),
-            "name".to_string(),
-            "price".to_string(),
-            "price_change".to_string(),
-            "price_change_rate".to_string(),
-            "update_time".to_string(),
-        ]
+/// `/events?code=`: news, disclosures and earnings dates of a single stock,
+/// oldest first as JSON, or as an Atom (`format=atom`) or RSS (`format=rss`) feed.
+async fn handle_events(req: Request, env: &Env) -> Result<Response> {
+    let url = req.url()?;
+    let query_params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+
+    let code = match parse_codes(&query_params) {
+        Ok(codes) if codes.len() == 1 => codes[0].clone(),
+        Ok(_) => return Response::error("Events are available for one code at a time.", 400),
+        Err(message) => return Response::error(message, 400),
     };
 
-    for key in &keys_to_process {
-        let value = match key.as_str() {
-            "code" => Some(code.to_string()),
-            _ => {
-                if let Some(selector_str) = selector_map.get(key.as_str()) {
-                    let selector = Selector::parse(selector_str).unwrap();
-                    document.select(&selector).next().map(|el| el.text().collect::<String>().trim().to_string())
-                } else {
-                    None
-                }
-            }
-        };
-        if let Some(val) = value {
-            results.insert(key.clone(), Value::String(val));
-        }
+    let format = query_params.get("format").map(String::as_str).unwrap_or("json");
+    if !matches!(format, "json" | "atom" | "rss") {
+        return Response::error(format!("Unknown format '{}'. Use 'json', 'atom' or 'rss'.", format), 400);
     }
-    
-    // Ensure essential keys are present if requested, or if no keys were requested (defaults used)
-    if keys_to_process.contains(&"name".to_string()) && !results.contains_key("name") {
-         return Err(worker::Error::from("Failed to scrape essential data (name) from DOM."));
+
+    let symbol = match code.parse::<Symbol>() {
+        Ok(symbol) => symbol,
+        Err(e) => return Response::error(e.to_string(), 400),
+    };
+    let events = upstream(env).fetch_symbol_page(&symbol, "").await.and_then(|page| {
+        let state = extract_preloaded_state(&page.body)?;
+        Ok((extract_events(&code, &state, page.fetched_at), page))
+    });
+    let age = events.as_ref().ok().and_then(|(_, page)| page.age);
+    let cache_control = cache_control(std::slice::from_ref(&code), events.is_ok(), age);
+
+    let (body, content_type) = match (format, events) {
+        ("json", events) => {
+            let result = match events {
+                Ok((events, page)) => CodeResult::new(code, Some(events), None).served(page.age),
+                Err(e) => CodeResult::new(code, None, Some(e.into())),
+            };
+            return json_response(&result, &cache_control);
+        }
+        (_, Err(e)) => return Response::error(e.to_string(), 502),
+        ("atom", Ok((events, page))) => (events_to_atom(&code, &events, page.fetched_at), "application/atom+xml; charset=utf-8"),
+        (_, Ok((events, _))) => (events_to_rss(&code, &events, &symbol.quote_url()), "application/rss+xml; charset=utf-8"),
+    };
+    let mut headers = Headers::new();
+    headers.set("Content-Type", content_type)?;
+    headers.set("Cache-Control", &cache_control)?;
+    Ok(Response::ok(body)?.with_headers(headers))
+}
+
+/// `/history?code=&page=`: daily OHLCV bars for a single stock, as JSON or CSV.
+async fn handle_history(req: Request, env: &Env) -> Result<Response> {
+    let url = req.url()?;
+    let query_params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+
+    let (code, pages) = match parse_paged_query(&query_params) {
+        Ok(parsed) => parsed,
+        Err(message) => return Response::error(message, 400),
+    };
+
+    let csv = match query_params.get("format").map(String::as_str) {
+        None | Some("json") => false,
+        Some("csv") => true,
+        Some(other) => return Response::error(format!("Unknown format '{}'. Use 'json' or 'csv'.", other), 400),
+    };
+
+    let result = upstream(env).fetch_paged(code.clone(), "history", pages, |state, _| extract_price_history(state)).await;
+    let cache_control = cache_control(&[code], result.error.is_none(), result.age);
+    if !csv {
+        return json_response(&result, &cache_control);
     }
-    if keys_to_process.contains(&"price".to_string()) && !results.contains_key("price") {
-         return Err(worker::Error::from("Failed to scrape essential data (price) from DOM."));
+    match result {
+        CodeResult { data: Some(history), error: None, .. } => {
+            let mut headers = Headers::new();
+            headers.set("Content-Type", "text/csv; charset=utf-8")?;
+            headers.set("Cache-Control", &cache_control)?;
+            Ok(Response::ok(history.to_csv())?.with_headers(headers))
+        }
+        CodeResult { error, .. } => Response::error(error.unwrap_or_default(), 502),
     }
+}
+
+/// `/margin?code=&page=`: credit balances, margin ratio and weekly changes for a single stock.
+async fn handle_margin(req: Request, env: &Env) -> Result<Response> {
+    let url = req.url()?;
+    let query_params: HashMap<String, String> = url.query_pairs().into_owned().collect();
 
-    results.insert("status".to_string(), Value::String("OK".to_string()));
-    results.insert("source".to_string(), Value::String("dom_fallback".to_string()));
+    let (code, pages) = match parse_paged_query(&query_params) {
+        Ok(parsed) => parsed,
+        Err(message) => return Response::error(message, 400),
+    };
 
-    Ok(results)
+    let result: CodeResult<MarginHistory> =
+        upstream(env).fetch_paged(code.clone(), "margin", pages, extract_margin_history).await;
+    json_response(&result, &cache_control(&[code], result.error.is_none(), result.age))
 }
 
-use std::collections::HashMap;
-// --- Helper Functions ---
-
-fn get_data_sources() -> Vec<DataSource> {
-    vec![
-        DataSource {
-            path: &["mainStocksPriceBoard", "priceBoard"],
-            mappings: HashMap::from([
-                ("code", "code"),
-                ("name", "name"),
-                ("price", "price"),
-                ("price_change", "priceChange"),
-                ("price_change_rate", "priceChangeRate"),
-                ("update_time", "priceDateTime"),
-            ]),
-            strip_suffix: true,
-        },
-        DataSource {
-            path: &["mainCurrencyPriceBoard", "currencyPrices"],
-            mappings: HashMap::from([
-                ("code", "currencyPairCode"),
-                ("name", "currencyPairName"),
-                ("price", "bid"),
-                ("price_change", "priceChange"),
-                ("price_change_rate", "priceChangeRate"),
-                ("update_time", "priceUpdateTime"),
-            ]),
-            strip_suffix: false,
-        },
-        DataSource {
-            path: &["mainDomesticIndexPriceBoard", "indexPrices"],
-            mappings: HashMap::from([
-                ("code", "code"),
-                ("name", "name"),
-                ("price", "price"),
-                ("price_change", "changePrice"),
-                ("price_change_rate", "changePriceRate"),
-                ("update_time", "japanUpdateTime"),
-            ]),
-            strip_suffix: false,
+/// Parses the single `code` and the `page` parameter of a paged section route.
+/// `page` is a 1-based page number (default 1) or `all`.
+fn parse_paged_query(query_params: &HashMap<String, String>) -> std::result::Result<(String, PageRequest), String> {
+    let code = match parse_codes(query_params)?.as_slice() {
+        [code] => code.clone(),
+        _ => return Err("This route accepts one code at a time.".to_string()),
+    };
+
+    let pages = match query_params.get("page").map(String::as_str) {
+        None => PageRequest { first: 1, follow: false },
+        Some("all") => PageRequest { first: 1, follow: true },
+        Some(page) => match page.parse::<u32>() {
+            Ok(first) if first > 0 => PageRequest { first, follow: false },
+            _ => return Err(format!("Invalid page '{}'. Use a page number or 'all'.", page)),
         },
-    ]
+    };
+    Ok((code, pages))
 }
 
-fn find_object<'a>(value: &'a Value, path: &[&str]) -> Option<&'a Map<String, Value>> {
-    let mut current = value;
-    for key in path {
-        current = current.get(key)?;
+/// Parses the `keys` and `format` query parameters of quote output.
+fn parse_output_params(
+    query_params: &HashMap<String, String>,
+) -> std::result::Result<(Option<Vec<String>>, Format), String> {
+    let keys: Option<Vec<String>> = query_params
+        .get("keys")
+        .map(|s| s.split(',').map(|k| k.trim().to_string()).collect());
+
+    let format = match query_params.get("format").map(String::as_str) {
+        None | Some("raw") => Format::Raw,
+        Some("typed") => Format::Typed,
+        Some(other) => return Err(format!("Unknown format '{}'. Use 'raw' or 'typed'.", other)),
+    };
+    Ok((keys, format))
+}
+
+/// Parses the comma-separated `code` query parameter.
+fn parse_codes(query_params: &HashMap<String, String>) -> std::result::Result<Vec<String>, &'static str> {
+    let codes_str = query_params
+        .get("code")
+        .ok_or("Query parameter 'code' is required. e.g., ?code=7203.T,^DJI")?;
+
+    let codes: Vec<String> = codes_str
+        .split(',')
+        .filter(|s| !s.is_empty())
+        .map(|s| s.trim().to_string())
+        .collect();
+
+    if codes.is_empty() {
+        return Err("Query parameter 'code' cannot be empty.");
     }
-    current.as_object()
+    Ok(codes)
 }
 
-fn get_string_value<'a>(obj: &'a Map<String, Value>, key: &str) -> Option<&'a str> {
-    obj.get(key)?.as_str()
+/// The current time, from the Workers runtime clock.
+fn now() -> DateTime<Utc> {
+    DateTime::from_timestamp_millis(Date::now().as_millis() as i64).unwrap_or_default()
 }
 
-fn find_object_paths<'a>(
-    value: &'a Value,
-    keys_to_find: &[String],
-    current_path: &mut Vec<&'a str>,
-    found_paths: &mut Vec<Vec<&'a str>>,
-) {
-    if let Value::Object(map) = value {
-        if keys_to_find.iter().all(|key| map.contains_key(key)) {
-            found_paths.push(current_path.clone());
-        }
-        for (key, nested_value) in map {
-            current_path.push(key);
-            find_object_paths(nested_value, keys_to_find, current_path, found_paths);
-            current_path.pop();
-        }
-    } else if let Value::Array(arr) = value {
-        for nested_value in arr {
-            find_object_paths(nested_value, keys_to_find, current_path, found_paths);
-        }
+/// Age in seconds of the oldest cached page behind `results`.
+fn oldest<T>(results: &[CodeResult<T>]) -> Option<u64> {
+    results.iter().filter_map(|result| result.age).max()
+}
+
+/// `Cache-Control` for a response about `codes`: the shortest cache lifetime
+/// among them, less the `age` of the oldest cached page it was read from, or
+/// `no-store` when some code failed.
+fn cache_control(codes: &[String], all_ok: bool, age: Option<u64>) -> String {
+    if !all_ok {
+        return "no-store".to_string();
     }
-}
\ No newline at end of file
+    let now = now();
+    let max_age = codes
+        .iter()
+        .filter_map(|code| code.parse::<Symbol>().ok())
+        .map(|symbol| cache_ttl(&symbol, now))
+        .min()
+        .unwrap_or(TRADING_TTL_SECONDS);
+    format!("public, max-age={}", u64::from(max_age).saturating_sub(age.unwrap_or(0)))
+}
+
+/// Serializes `body` as JSON with a `Cache-Control` header.
+fn json_response<T: Serialize>(body: &T, cache_control: &str) -> Result<Response> {
+    let mut response = Response::from_json(body)?;
+    response.headers_mut().set("Cache-Control", cache_control)?;
+    Ok(response)
+}
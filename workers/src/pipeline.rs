@@ -0,0 +1,352 @@
+//! The fetch-and-extract pipeline behind the routes: pages of a symbol fetched
+//! through the page cache, paced by the scheduler, and read into one
+//! `CodeResult` per requested code.
+//!
+//! The runtime comes in through [`Upstream`], so the pipeline also runs
+//! natively against fixture or replayed pages.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use chrono::{DateTime, Utc};
+use preloaded_state::{
+    cache_key, cache_ttl, distinct_symbols, extract_chart, extract_preloaded_state, extract_usd_jpy, locate_preloaded_state,
+    process_dom_data, process_json_data, verify_sources, CachedPage, Chart, Clock, Consistency, DataSource, FetchScheduler,
+    Format, OutputOptions, PageFetcher, PageStore, Paged, StateError, Symbol, SymbolError, UpstreamError,
+};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Most pages of a paged section fetched for `page=all`, to stay within the
+/// worker's subrequest limit.
+pub const MAX_PAGES: u32 = 20;
+
+/// The currency page whose bid converts US prices to yen.
+const USD_JPY_CODE: &str = "USDJPY=X";
+
+/// Represents the final JSON response for a single code.
+#[derive(Serialize, Debug)]
+pub struct CodeResult<T = Map<String, Value>> {
+    pub(crate) code: String,
+    pub(crate) data: Option<T>,
+    pub(crate) error: Option<String>,
+    /// Machine-readable kind of `error`, e.g. `rate_limited`, `blocked`,
+    /// `state_missing` or `invalid_code` (see `Failure`).
+    pub(crate) error_kind: Option<&'static str>,
+    /// Whether `data` was read from a cached page.
+    pub(crate) cached: bool,
+    /// Seconds since the cached page was fetched.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) age: Option<u64>,
+    /// How the embedded state and the DOM compare, with `verify=true`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) consistency: Option<Consistency>,
+}
+
+impl<T> CodeResult<T> {
+    pub(crate) fn new(code: String, data: Option<T>, error: Option<Failure>) -> Self {
+        let (error, error_kind) = match error {
+            Some(failure) => (Some(failure.message), Some(failure.kind)),
+            None => (None, None),
+        };
+        CodeResult { code, data, error, error_kind, cached: false, age: None, consistency: None }
+    }
+
+    /// Marks the result as read from a cached page of the given age.
+    pub(crate) fn served(mut self, age: Option<u64>) -> Self {
+        self.cached = age.is_some();
+        self.age = age;
+        self
+    }
+}
+
+/// Why a code has no (complete) data: an `UpstreamError` kind, or one of
+/// `invalid_code`, `section_missing` and `term_mismatch`.
+#[derive(Debug, Clone)]
+pub(crate) struct Failure {
+    pub(crate) kind: &'static str,
+    pub(crate) message: String,
+}
+
+impl Failure {
+    pub(crate) fn new(kind: &'static str, message: impl Into<String>) -> Self {
+        Failure { kind, message: message.into() }
+    }
+}
+
+impl From<UpstreamError> for Failure {
+    fn from(error: UpstreamError) -> Self {
+        Failure::new(error.kind(), error.to_string())
+    }
+}
+
+impl From<SymbolError> for Failure {
+    fn from(error: SymbolError) -> Self {
+        Failure::new("invalid_code", error.to_string())
+    }
+}
+
+/// Pages requested from a paged section.
+#[derive(Clone, Copy)]
+pub struct PageRequest {
+    pub first: u32,
+    /// Follow `paging` up to `MAX_PAGES` pages.
+    pub follow: bool,
+}
+
+/// A page body, with its age in seconds when it came from the cache.
+pub struct Page {
+    pub(crate) body: String,
+    /// When Yahoo served the page; completes partial update times.
+    pub(crate) fetched_at: DateTime<Utc>,
+    pub(crate) age: Option<u64>,
+}
+
+/// Where pages come from: the page cache in front of a fetcher, paced and
+/// retried by a scheduler.
+pub struct Upstream<C: Clock> {
+    pub cache: Box<dyn PageStore>,
+    pub fetcher: Box<dyn PageFetcher>,
+    pub scheduler: Rc<FetchScheduler<C>>,
+    /// The current time, for cache freshness and `fetched_at`.
+    pub now: fn() -> DateTime<Utc>,
+}
+
+impl<C: Clock> Upstream<C> {
+    /// Fetches a page of `symbol` through the cache. `section` is the path under
+    /// the quote page (`""` for the quote page itself, `"history?page=2"`, ...).
+    /// Only pages that embed `__PRELOADED_STATE__` are cached.
+    pub async fn fetch_symbol_page(&self, symbol: &Symbol, section: &str) -> Result<Page, UpstreamError> {
+        let now = (self.now)();
+        let key = cache_key(symbol, if section.is_empty() { "quote" } else { section });
+        if let Some(page) = self.cache.get(&key, now).await {
+            return Ok(Page { age: Some(page.age(now)), fetched_at: page.stored_at, body: page.body });
+        }
+
+        let url = format!("{}{}", symbol.quote_url(), section);
+        let body = self.scheduler.fetch(&self.fetcher, &url).await?;
+        if locate_preloaded_state(&body).is_ok() {
+            self.cache.put(&key, CachedPage::new(body.clone(), now, cache_ttl(symbol, now))).await;
+        }
+        Ok(Page { body, fetched_at: now, age: None })
+    }
+
+    /// Parses `code` and fetches one of its pages through the cache.
+    async fn fetch_code_page(&self, code: &str, section: &str) -> Result<Page, Failure> {
+        let symbol = code.parse::<Symbol>()?;
+        Ok(self.fetch_symbol_page(&symbol, section).await?)
+    }
+
+    /// Fetches the quote page of each distinct symbol among `codes` once, keyed by
+    /// canonical symbol, so `7203` and `7203.T` in one batch share a fetch. Codes
+    /// that do not parse are left out.
+    async fn fetch_quote_pages(&self, codes: &[String]) -> HashMap<String, Result<Page, UpstreamError>> {
+        let symbols = distinct_symbols(codes);
+        let pages = self.scheduler.run(symbols.iter().map(|symbol| self.fetch_symbol_page(symbol, ""))).await;
+        symbols.iter().map(Symbol::canonical).zip(pages).collect()
+    }
+
+    /// Fetches the quote page of a single code and extracts one typed section.
+    pub async fn fetch_section<T>(&self, code: String, extract: fn(&Value) -> Option<T>, missing: &str) -> CodeResult<T> {
+        let page = self.fetch_code_page(&code, "").await;
+        section_result(code, page.as_ref().map_err(Failure::clone), extract, missing)
+    }
+
+    /// Extracts one typed section for each code, in the order and spelling given,
+    /// fetching each distinct page once.
+    pub async fn fetch_sections<T>(
+        &self,
+        codes: &[String],
+        extract: fn(&Value) -> Option<T>,
+        missing: &str,
+    ) -> Vec<CodeResult<T>> {
+        let pages = self.fetch_quote_pages(codes).await;
+        codes
+            .iter()
+            .map(|code| section_result(code.clone(), quote_page(&pages, code).map(|(_, page)| page), extract, missing))
+            .collect()
+    }
+
+    /// Fetches a paged section starting at `pages.first`, following `paging`
+    /// when requested. If a later page fails, the entries fetched so far are
+    /// returned along with the error. The reported age is the oldest cached page's.
+    pub async fn fetch_paged<T: Paged>(
+        &self,
+        code: String,
+        section: &str,
+        pages: PageRequest,
+        extract: fn(&Value, DateTime<Utc>) -> Option<T>,
+    ) -> CodeResult<T> {
+        let (mut data, mut age) = match self.fetch_section_page(&code, section, pages.first, extract).await {
+            Ok(first) => first,
+            Err(e) => return CodeResult::new(code, None, Some(e)),
+        };
+
+        let mut fetched = 1;
+        while let Some(next) = data.paging().next_page().filter(|_| pages.follow && fetched < MAX_PAGES) {
+            match self.fetch_section_page(&code, section, next, extract).await {
+                Ok((page, page_age)) => {
+                    data.merge(page);
+                    age = age.max(page_age);
+                }
+                Err(e) => {
+                    let error = Failure::new(e.kind, format!("Stopped at page {}: {}", next, e.message));
+                    return CodeResult::new(code, Some(data), Some(error)).served(age);
+                }
+            }
+            fetched += 1;
+        }
+
+        CodeResult::new(code, Some(data), None).served(age)
+    }
+
+    async fn fetch_section_page<T>(
+        &self,
+        code: &str,
+        section: &str,
+        page: u32,
+        extract: fn(&Value, DateTime<Utc>) -> Option<T>,
+    ) -> Result<(T, Option<u64>), Failure> {
+        let page = self.fetch_code_page(code, &format!("{}?page={}", section, page)).await?;
+        let state = extract_preloaded_state(&page.body).map_err(UpstreamError::from)?;
+        let data = extract(&state, page.fetched_at).ok_or_else(|| Failure::new("section_missing", format!("No {} data in page.", section)))?;
+        Ok((data, page.age))
+    }
+
+    /// Fetches the chart of `code`, from the chart page of `term` when one is
+    /// requested. A page embedding a different term than requested is reported
+    /// as an error alongside the data it does have.
+    pub async fn fetch_chart(&self, code: String, term: Option<String>) -> CodeResult<Chart> {
+        let section = term.as_ref().map(|term| format!("chart?term={}", term)).unwrap_or_default();
+        let page = match self.fetch_code_page(&code, &section).await {
+            Ok(page) => page,
+            Err(e) => return CodeResult::new(code, None, Some(e)),
+        };
+
+        let chart = extract_preloaded_state(&page.body)
+            .map_err(|e| Failure::from(UpstreamError::from(e)))
+            .and_then(|state| extract_chart(&state).ok_or_else(|| Failure::new("section_missing", "No chart data in page.")));
+        match chart {
+            Ok(chart) => {
+                let error = match (&term, &chart.term) {
+                    (Some(requested), Some(embedded)) if requested != embedded => Some(Failure::new(
+                        "term_mismatch",
+                        format!("Requested term '{}' but the page embeds '{}'.", requested, embedded),
+                    )),
+                    _ => None,
+                };
+                CodeResult::new(code, Some(chart), error).served(page.age)
+            }
+            Err(e) => CodeResult::new(code, None, Some(e)),
+        }
+    }
+
+    /// Fetches price board data for each code, in the order and spelling given.
+    /// Each distinct page is fetched once, the USD/JPY page included when the
+    /// request needs the rate. With `verify` each page is also read both ways
+    /// and the readings compared.
+    pub async fn fetch_quotes(
+        &self,
+        codes: &[String],
+        keys: Option<Vec<String>>,
+        format: Format,
+        verify: bool,
+        data_sources: &[DataSource],
+    ) -> Vec<CodeResult> {
+        let needs_usd_jpy = usd_jpy_needed(codes, keys.as_ref());
+        let pages = if needs_usd_jpy {
+            let mut batch = codes.to_vec();
+            batch.push(USD_JPY_CODE.to_string());
+            self.fetch_quote_pages(&batch).await
+        } else {
+            self.fetch_quote_pages(codes).await
+        };
+        let usd_jpy = needs_usd_jpy.then(|| usd_jpy_rate(&pages)).flatten();
+        codes
+            .iter()
+            .map(|code| {
+                let (symbol, page) = match quote_page(&pages, code) {
+                    Ok(found) => found,
+                    Err(e) => return CodeResult::new(code.clone(), None, Some(e)),
+                };
+                let output = OutputOptions {
+                    keys: keys.clone(),
+                    format,
+                    fetched_at: page.fetched_at,
+                    usd_jpy,
+                };
+                let result = quote_result(code.clone(), &symbol, page, &output, data_sources);
+                if !verify {
+                    return result;
+                }
+                let consistency = verify_sources(code, &symbol, &page.body, data_sources, page.fetched_at);
+                CodeResult { consistency: Some(consistency), ..result }
+            })
+            .collect()
+    }
+}
+
+/// Whether the request can use the USD/JPY rate: `jpy_price` or `usd_jpy` was
+/// requested, or a US ticker is asked for without `keys`.
+fn usd_jpy_needed(codes: &[String], keys: Option<&Vec<String>>) -> bool {
+    match keys {
+        Some(keys) => keys.iter().any(|key| key == "jpy_price" || key == "usd_jpy"),
+        None => codes.iter().any(|code| matches!(code.parse(), Ok(Symbol::UsStock(_)))),
+    }
+}
+
+/// The USD/JPY rate from the batch's `USDJPY=X` page.
+fn usd_jpy_rate(pages: &HashMap<String, Result<Page, UpstreamError>>) -> Option<Decimal> {
+    let (_, page) = quote_page(pages, USD_JPY_CODE).ok()?;
+    extract_usd_jpy(&extract_preloaded_state(&page.body).ok()?)
+}
+
+/// The page `fetch_quote_pages` fetched for `code`, or why there is none.
+fn quote_page<'a>(
+    pages: &'a HashMap<String, Result<Page, UpstreamError>>,
+    code: &str,
+) -> Result<(Symbol, &'a Page), Failure> {
+    let symbol = code.parse::<Symbol>()?;
+    match pages.get(&symbol.canonical()) {
+        Some(Ok(page)) => Ok((symbol, page)),
+        Some(Err(e)) => Err(e.clone().into()),
+        None => Err(Failure::new("network", format!("No page was fetched for {}.", symbol))),
+    }
+}
+
+fn section_result<T>(
+    code: String,
+    page: Result<&Page, Failure>,
+    extract: fn(&Value) -> Option<T>,
+    missing: &str,
+) -> CodeResult<T> {
+    let page = match page {
+        Ok(page) => page,
+        Err(e) => return CodeResult::new(code, None, Some(e)),
+    };
+
+    let section = extract_preloaded_state(&page.body)
+        .map_err(|e| Failure::from(UpstreamError::from(e)))
+        .and_then(|state| extract(&state).ok_or_else(|| Failure::new("section_missing", missing)));
+
+    match section {
+        Ok(data) => CodeResult::new(code, Some(data), None).served(page.age),
+        Err(e) => CodeResult::new(code, None, Some(e)),
+    }
+}
+
+/// Processes a fetched quote page for a single code.
+fn quote_result(code: String, symbol: &Symbol, page: &Page, output: &OutputOptions, data_sources: &[DataSource]) -> CodeResult {
+    let result_data = match extract_preloaded_state(&page.body) {
+        Ok(data) => process_json_data(&code, symbol, &data, data_sources, output),
+        // __PRELOADED_STATE__ script not found, fallback to DOM
+        Err(StateError::NotFound) => process_dom_data(&code, &page.body, output),
+        Err(e) => Err(e.into()),
+    };
+
+    match result_data {
+        Ok(data) => CodeResult::new(code, Some(data), None).served(page.age),
+        Err(e) => CodeResult::new(code, None, Some(e.into())),
+    }
+}
@@ -0,0 +1,67 @@
+//! The fetch scheduler shared by every request an isolate serves, so the
+//! per-host rate limit holds across concurrent requests.
+//!
+//! Configured from optional variables: `FETCH_MAX_CONCURRENCY`,
+//! `FETCH_RATE_PER_SECOND`, `FETCH_BURST`, `FETCH_JITTER_MS`,
+//! `FETCH_MAX_RETRIES` and `FETCH_RETRY_BASE_MS`.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::time::Duration;
+
+use futures::future::LocalBoxFuture;
+use preloaded_state::{Clock, FetchScheduler, SchedulerConfig};
+use worker::{Date, Delay, Env};
+
+pub struct WorkerClock;
+
+impl Clock for WorkerClock {
+    fn now_millis(&self) -> u64 {
+        Date::now().as_millis()
+    }
+
+    fn sleep(&self, millis: u64) -> LocalBoxFuture<'static, ()> {
+        Box::pin(Delay::from(Duration::from_millis(millis)))
+    }
+}
+
+thread_local! {
+    static SCHEDULER: RefCell<Option<Rc<FetchScheduler<WorkerClock>>>> = const { RefCell::new(None) };
+}
+
+/// Creates the isolate's scheduler from `env` on the first request.
+pub fn configure(env: &Env) {
+    SCHEDULER.with(|scheduler| {
+        scheduler
+            .borrow_mut()
+            .get_or_insert_with(|| Rc::new(FetchScheduler::new(config_from_env(env), WorkerClock)));
+    });
+}
+
+/// The isolate's scheduler, with the default configuration if `configure`
+/// has not run.
+pub fn scheduler() -> Rc<FetchScheduler<WorkerClock>> {
+    SCHEDULER.with(|scheduler| {
+        scheduler
+            .borrow_mut()
+            .get_or_insert_with(|| Rc::new(FetchScheduler::new(SchedulerConfig::default(), WorkerClock)))
+            .clone()
+    })
+}
+
+fn config_from_env(env: &Env) -> SchedulerConfig {
+    fn var<T: FromStr>(env: &Env, name: &str) -> Option<T> {
+        env.var(name).ok()?.to_string().trim().parse().ok()
+    }
+
+    let default = SchedulerConfig::default();
+    SchedulerConfig {
+        max_concurrency: var(env, "FETCH_MAX_CONCURRENCY").unwrap_or(default.max_concurrency),
+        requests_per_second: var(env, "FETCH_RATE_PER_SECOND").unwrap_or(default.requests_per_second),
+        burst: var(env, "FETCH_BURST").unwrap_or(default.burst),
+        max_jitter_millis: var(env, "FETCH_JITTER_MS").unwrap_or(default.max_jitter_millis),
+        max_retries: var(env, "FETCH_MAX_RETRIES").unwrap_or(default.max_retries),
+        retry_base_millis: var(env, "FETCH_RETRY_BASE_MS").unwrap_or(default.retry_base_millis),
+    }
+}
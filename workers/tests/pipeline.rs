@@ -0,0 +1,183 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use chrono::{DateTime, Utc};
+use futures::executor::block_on;
+use futures::future::{self, LocalBoxFuture};
+use preloaded_state::{
+    extract_price_history, parse_data_sources, Clock, FetchScheduler, FixtureFetcher, Format, MemoryCache, PageFetcher,
+    Recording, ReplayFetcher, SchedulerConfig, UpstreamError,
+};
+use serde_json::{json, Value};
+use workers::pipeline::{PageRequest, Upstream};
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../fixtures");
+const HISTORY_URL: &str = "https://finance.yahoo.co.jp/quote/5016.T/history?page=";
+
+/// With no rate limit and no jitter nothing waits.
+struct Immediate;
+
+impl Clock for Immediate {
+    fn now_millis(&self) -> u64 {
+        0
+    }
+
+    fn sleep(&self, _millis: u64) -> LocalBoxFuture<'static, ()> {
+        Box::pin(future::ready(()))
+    }
+}
+
+/// Passes requests on and keeps the URLs asked for.
+struct Counting<F> {
+    inner: F,
+    urls: Rc<RefCell<Vec<String>>>,
+}
+
+impl<F: PageFetcher> PageFetcher for Counting<F> {
+    fn fetch<'a>(&'a self, url: &'a str) -> LocalBoxFuture<'a, Result<String, UpstreamError>> {
+        self.urls.borrow_mut().push(url.to_string());
+        self.inner.fetch(url)
+    }
+}
+
+fn now() -> DateTime<Utc> {
+    "2025-10-16T06:30:00Z".parse().unwrap()
+}
+
+fn upstream(fetcher: impl PageFetcher + 'static) -> (Upstream<Immediate>, Rc<RefCell<Vec<String>>>) {
+    let urls = Rc::new(RefCell::new(Vec::new()));
+    let config = SchedulerConfig {
+        requests_per_second: 0.0,
+        max_jitter_millis: 0,
+        ..SchedulerConfig::default()
+    };
+    let upstream = Upstream {
+        cache: Box::new(RefCell::new(MemoryCache::default())),
+        fetcher: Box::new(Counting { inner: fetcher, urls: urls.clone() }),
+        scheduler: Rc::new(FetchScheduler::new(config, Immediate)),
+        now,
+    };
+    (upstream, urls)
+}
+
+fn codes(codes: &[&str]) -> Vec<String> {
+    codes.iter().map(|code| code.to_string()).collect()
+}
+
+/// A history page embedding `dates`, page `page` of 3.
+fn history_page(page: u32, has_next: bool, dates: &[&str]) -> String {
+    let histories: Vec<_> = dates
+        .iter()
+        .map(|d| json!({ "baseDatetime": d, "openPrice": "2,010", "highPrice": "2,045", "lowPrice": "1,884.5", "closePrice": "1,950", "volume": "36,936,000" }))
+        .collect();
+    let state = json!({
+        "mainStocksHistory": {
+            "history": { "histories": histories },
+            "paging": { "page": page, "totalPage": 3, "totalSize": 6, "hasNext": has_next }
+        }
+    });
+    format!("<html><body><script>window.__PRELOADED_STATE__ = {}</script></body></html>", state)
+}
+
+fn bar_dates(result: &Value) -> Vec<&str> {
+    result["data"]["bars"].as_array().unwrap().iter().map(|bar| bar["date"].as_str().unwrap()).collect()
+}
+
+#[test]
+fn quotes_follow_the_request_and_fetch_each_symbol_once() {
+    let (upstream, urls) = upstream(FixtureFetcher::new(FIXTURES));
+    let requested = codes(&["8951", "5016.T", "5016", "9999.T", "not a code"]);
+    let keys = Some(vec!["price".to_string()]);
+    let results = block_on(upstream.fetch_quotes(&requested, keys, Format::Raw, false, &[]));
+    let results = serde_json::to_value(&results).unwrap();
+
+    let returned: Vec<_> = results.as_array().unwrap().iter().map(|result| result["code"].as_str().unwrap()).collect();
+    assert_eq!(returned, ["8951", "5016.T", "5016", "9999.T", "not a code"]);
+    assert_eq!(
+        *urls.borrow(),
+        [
+            "https://finance.yahoo.co.jp/quote/8951.T/",
+            "https://finance.yahoo.co.jp/quote/5016.T/",
+            "https://finance.yahoo.co.jp/quote/9999.T/",
+        ]
+    );
+
+    for result in &results.as_array().unwrap()[..3] {
+        assert!(result["data"]["price"].is_string(), "{}", result);
+        assert_eq!(result["error"], Value::Null);
+        assert_eq!(result["cached"], false);
+    }
+    assert_eq!(results[1]["data"], results[2]["data"]);
+    assert_eq!(results[3]["error_kind"], "http_status");
+    assert_eq!(results[4]["error_kind"], "invalid_code");
+}
+
+#[test]
+fn the_usd_jpy_page_is_shared_with_a_requested_usd_jpy_quote() {
+    let (upstream, urls) = upstream(FixtureFetcher::new(FIXTURES));
+    let data_sources = parse_data_sources(include_str!("../data_sources.toml")).unwrap();
+    let requested = codes(&["AAPL", "USDJPY=X"]);
+    let results = block_on(upstream.fetch_quotes(&requested, None, Format::Raw, false, &data_sources));
+    let results = serde_json::to_value(&results).unwrap();
+
+    assert_eq!(
+        *urls.borrow(),
+        ["https://finance.yahoo.co.jp/quote/AAPL/", "https://finance.yahoo.co.jp/quote/USDJPY=X/"]
+    );
+    assert!(results[0]["data"]["jpy_price"].is_string(), "{}", results[0]);
+    assert!(results[1]["data"]["bid"].is_string(), "{}", results[1]);
+}
+
+#[test]
+fn repeated_quotes_are_served_from_the_store() {
+    let (upstream, urls) = upstream(FixtureFetcher::new(FIXTURES));
+    let first = block_on(upstream.fetch_quotes(&codes(&["5016.T"]), None, Format::Raw, false, &[]));
+    let second = block_on(upstream.fetch_quotes(&codes(&["5016"]), None, Format::Raw, false, &[]));
+
+    assert_eq!(urls.borrow().len(), 1);
+    let (first, second) = (serde_json::to_value(&first).unwrap(), serde_json::to_value(&second).unwrap());
+    assert_eq!(second[0]["cached"], true);
+    assert_eq!(second[0]["age"], 0);
+    assert_eq!(first[0]["data"]["price"], second[0]["data"]["price"]);
+}
+
+#[test]
+fn all_pages_are_followed_and_merged() {
+    let mut recording = Recording::default();
+    recording.insert(format!("{}1", HISTORY_URL), history_page(1, true, &["2025/10/16", "2025/10/15"]));
+    recording.insert(format!("{}2", HISTORY_URL), history_page(2, true, &["2025/10/14", "2025/10/10"]));
+    recording.insert(format!("{}3", HISTORY_URL), history_page(3, false, &["2025/10/09"]));
+    let (upstream, urls) = upstream(ReplayFetcher::new(recording));
+
+    let pages = PageRequest { first: 1, follow: true };
+    let result = block_on(upstream.fetch_paged("5016.T".to_string(), "history", pages, |state, _| extract_price_history(state)));
+    let result = serde_json::to_value(&result).unwrap();
+
+    assert_eq!(result["error"], Value::Null);
+    assert_eq!(bar_dates(&result), ["2025-10-09", "2025-10-10", "2025-10-14", "2025-10-15", "2025-10-16"]);
+    assert_eq!(urls.borrow().len(), 3);
+}
+
+#[test]
+fn paging_stops_at_a_failed_page_with_what_it_has() {
+    let mut recording = Recording::default();
+    recording.insert(format!("{}2", HISTORY_URL), history_page(2, true, &["2025/10/14", "2025/10/10"]));
+    let (upstream, urls) = upstream(ReplayFetcher::new(recording));
+
+    let pages = PageRequest { first: 2, follow: true };
+    let result = block_on(upstream.fetch_paged("5016.T".to_string(), "history", pages, |state, _| extract_price_history(state)));
+    let result = serde_json::to_value(&result).unwrap();
+
+    assert_eq!(bar_dates(&result), ["2025-10-10", "2025-10-14"]);
+    assert_eq!(result["error_kind"], "http_status");
+    assert!(result["error"].as_str().unwrap().starts_with("Stopped at page 3"), "{}", result);
+    assert_eq!(urls.borrow().len(), 2);
+
+    // Without `follow` only the requested page is read
+    let single = block_on(upstream.fetch_paged("5016.T".to_string(), "history", PageRequest { first: 2, follow: false }, |state, _| {
+        extract_price_history(state)
+    }));
+    let single = serde_json::to_value(&single).unwrap();
+    assert_eq!(single["error"], Value::Null);
+    assert_eq!(single["cached"], true);
+}